@@ -0,0 +1,127 @@
+//! Prints an annotated listing of PackStream bytes.
+//!
+//! ```text
+//! packstream-dump [--raw] [FILE...]
+//! ```
+//!
+//! Reads every `FILE` in turn, or the standard input when none is given or
+//! `FILE` is `-`. Input is read as text unless `--raw` is passed: either hex
+//! digits (`B1 71 91 01`, `0xB1, 0x71`, `b1719101`) or a Rust byte vector
+//! such as `[177, 113, 145, 1]`, as printed from `packer.out.buf`.
+use std::io::{self, Read};
+use std::process::exit;
+use packstream_core::disasm::disassemble;
+
+fn main() {
+    let mut raw = false;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--raw" => raw = true,
+            "-h" | "--help" => {
+                println!("usage: packstream-dump [--raw] [FILE...]");
+                return;
+            },
+            _ => paths.push(arg)
+        }
+    }
+    if paths.is_empty() {
+        paths.push("-".into());
+    }
+
+    let mut failed = false;
+    for path in paths.iter() {
+        let bytes = read(path).and_then(|input| {
+            if raw { Ok(input) } else { parse(&input) }
+        });
+        match bytes {
+            Ok(bytes) => {
+                if paths.len() > 1 {
+                    println!("{}:", path);
+                }
+                let listing = disassemble(&bytes);
+                print!("{}", listing);
+                failed |= listing.error.is_some();
+            },
+            Err(e) => {
+                eprintln!("packstream-dump: {}: {}", path, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        exit(1);
+    }
+}
+
+fn read(path: &str) -> io::Result<Vec<u8>> {
+    let mut input = Vec::new();
+    match path {
+        "-" => io::stdin().read_to_end(&mut input).map(|_| input),
+        _ => std::fs::read(path)
+    }
+}
+
+/// Parses hex digits, or decimal bytes when the text is a bracketed list.
+fn parse(input: &[u8]) -> io::Result<Vec<u8>> {
+    let text = std::str::from_utf8(input)
+        .map_err(|_| invalid("input is not text, use --raw"))?
+        .trim();
+
+    if text.starts_with('[') && text.ends_with(']') {
+        return text[1..text.len() - 1]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<u8>().map_err(|_| invalid(s)))
+            .collect();
+    }
+
+    let digits = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|s| s.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect::<String>();
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(invalid(&c.to_string()));
+    }
+    if digits.len() % 2 != 0 {
+        return Err(invalid("odd number of hex digits"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            let pair = &digits[i..i + 2];
+            u8::from_str_radix(pair, 16).map_err(|_| invalid(pair))
+        })
+        .collect()
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid input: {}", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_digits() {
+        assert_eq!(parse(b"B1 71 91 01\n").unwrap(), [0xB1, 0x71, 0x91, 0x01]);
+        assert_eq!(parse(b"0xB1, 0X71").unwrap(), [0xB1, 0x71]);
+        assert_eq!(parse(b"b1719101").unwrap(), [0xB1, 0x71, 0x91, 0x01]);
+        assert_eq!(parse(b"").unwrap(), []);
+    }
+
+    #[test]
+    fn parses_byte_vectors() {
+        assert_eq!(parse(b"[177, 113, 145, 1]").unwrap(), [177, 113, 145, 1]);
+        assert!(parse(b"[256]").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(parse(b"B17").is_err());
+        assert!(parse(b"B1 G1").is_err());
+        assert!(parse("a\u{e9}1".as_bytes()).is_err());
+        assert!(parse(b"\xFF").is_err());
+    }
+}
@@ -40,6 +40,18 @@ pub const STRUCT_NODE:u8                 = 0x4E;
 pub const STRUCT_RELATIONSHIP:u8         = 0x52;
 pub const STRUCT_PATH:u8                 = 0x50;
 pub const STRUCT_UNBOUND_RELATIONSHIP:u8 = 0x72;
+// TEMPORAL STRUCT SIGNATURES
+pub const STRUCT_DATE:u8                 = 0x44;
+pub const STRUCT_TIME:u8                 = 0x54;
+pub const STRUCT_LOCAL_TIME:u8           = 0x74;
+pub const STRUCT_DATE_TIME:u8            = 0x49;
+pub const STRUCT_DATE_TIME_ZONE_ID:u8    = 0x69;
+pub const STRUCT_LEGACY_DATE_TIME:u8     = 0x46;
+pub const STRUCT_LEGACY_DATE_TIME_ZONE_ID:u8 = 0x66;
+pub const STRUCT_LOCAL_DATE_TIME:u8      = 0x64;
+pub const STRUCT_DURATION:u8             = 0x45;
+pub const STRUCT_POINT_2D:u8             = 0x58;
+pub const STRUCT_POINT_3D:u8             = 0x59;
 // MESSAGE STRUCT SIGNATURES
 pub const MSG_INIT:u8                    = 0x01;
 pub const MSG_RUN:u8                     = 0x10;
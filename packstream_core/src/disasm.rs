@@ -0,0 +1,362 @@
+use std::fmt;
+use crate::consts::*;
+use crate::error::BoltError;
+use crate::scan::{header, Header};
+use crate::types::PackType;
+
+/// Maximum number of bytes printed on a single line.
+const MAX_LINE_BYTES: usize = 8;
+/// Maximum number of characters printed for a string value.
+const MAX_STRING_CHARS: usize = 48;
+
+/**
+ * A decoded value of a disassembled buffer.
+ */
+pub struct Entry {
+    /// Offset of the value marker in the buffer.
+    pub offset: usize,
+    /// Nesting level of the value, top level values being at 0.
+    pub depth: usize,
+    pub header: Header,
+}
+
+/**
+ * Annotated listing of a PackStream buffer, rendered by its
+ * [Display](std::fmt::Display) implementation as one line per value:
+ *
+ * ```text
+ * 0000  B3 4E               TINY_STRUCT   size=3 signature=0x4E Node
+ * 0002    01                TINY_INT      1
+ * 0003    91                TINY_LIST     size=1
+ * 0004      84 74 6F 74 6F  TINY_STRING   size=4 "toto"
+ * 0009    A0                TINY_MAP      size=0
+ * ```
+ *
+ * Decoding stops at the first invalid or truncated value, which is then
+ * reported on the last line.
+ */
+pub struct Disassembly<'a> {
+    pub buf: &'a [u8],
+    pub entries: Vec<Entry>,
+    pub error: Option<(usize, BoltError)>,
+}
+
+/**
+ * Disassembles every value of `buf`.
+ */
+pub fn disassemble(buf: &[u8]) -> Disassembly<'_> {
+    enum Pending { Items(usize), Stream }
+
+    let mut entries = Vec::new();
+    let mut stack: Vec<Pending> = Vec::new();
+    let mut offset = 0;
+    let error = loop {
+        let closing = match stack.last_mut() {
+            None if offset == buf.len() => break None,
+            None => false,
+            Some(Pending::Items(0)) => {
+                stack.pop();
+                continue;
+            },
+            Some(Pending::Items(n)) => {
+                *n -= 1;
+                false
+            },
+            Some(Pending::Stream) => buf.get(offset) == Some(&END_OF_STREAM)
+        };
+        if closing {
+            stack.pop();
+        }
+
+        let h = match header(&buf[offset..]) {
+            Ok(Some(h)) if h.marker == END_OF_STREAM && !closing =>
+                break Some((offset, BoltError::UnpackUnexpectedEndOfStream)),
+            Ok(Some(h)) if offset + h.len + h.bytes <= buf.len() => h,
+            Ok(_) => break Some((offset, BoltError::UnpackUnexpectedEndOfStream)),
+            Err(e) => break Some((offset, e))
+        };
+        entries.push(Entry { offset, depth: stack.len(), header: h });
+        offset += h.len + h.bytes;
+        if h.is_stream() {
+            stack.push(Pending::Stream);
+        }
+        else if h.items() > 0 {
+            stack.push(Pending::Items(h.items()));
+        }
+    };
+
+    Disassembly { buf, entries, error }
+}
+
+impl<'a> fmt::Display for Disassembly<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns = self.entries
+            .iter()
+            .map(|e| format!("{:indent$}{}", "", hex(self.buf, e),
+                             indent = e.depth * 2))
+            .collect::<Vec<_>>();
+        let width = columns.iter().map(String::len).max().unwrap_or(0);
+
+        for (e, column) in self.entries.iter().zip(columns) {
+            let h = &e.header;
+            let line = format!("{:04X}  {:<width$}  {:<14}{}",
+                               e.offset,
+                               column,
+                               marker_name(h.marker),
+                               describe(h, &self.buf[e.offset + h.len..]),
+                               width = width);
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        match &self.error {
+            Some((offset, e)) => writeln!(f, "{:04X}  error: {:?}", offset, e),
+            None => Ok(())
+        }
+    }
+}
+
+/**
+ * Gets the name of the constant matching the specified `marker`.
+ */
+pub fn marker_name(marker: u8) -> &'static str {
+    match marker {
+        TINY_INT_PF..=TINY_INT_PL
+        | TINY_INT_NF..=TINY_INT_NL => "TINY_INT",
+        NULL          => "NULL",
+        FLOAT_64      => "FLOAT_64",
+        FALSE         => "FALSE",
+        TRUE          => "TRUE",
+        INT_8         => "INT_8",
+        INT_16        => "INT_16",
+        INT_32        => "INT_32",
+        INT_64        => "INT_64",
+        BYTES_8       => "BYTES_8",
+        BYTES_16      => "BYTES_16",
+        BYTES_32      => "BYTES_32",
+        STRING_8      => "STRING_8",
+        STRING_16     => "STRING_16",
+        STRING_32     => "STRING_32",
+        LIST_8        => "LIST_8",
+        LIST_16       => "LIST_16",
+        LIST_32       => "LIST_32",
+        LIST_STREAM   => "LIST_STREAM",
+        MAP_8         => "MAP_8",
+        MAP_16        => "MAP_16",
+        MAP_32        => "MAP_32",
+        MAP_STREAM    => "MAP_STREAM",
+        STRUCT_8      => "STRUCT_8",
+        STRUCT_16     => "STRUCT_16",
+        END_OF_STREAM => "END_OF_STREAM",
+        b => match b & HIGH_NIBBLE {
+            TINY_STRING => "TINY_STRING",
+            TINY_LIST   => "TINY_LIST",
+            TINY_MAP    => "TINY_MAP",
+            TINY_STRUCT => "TINY_STRUCT",
+            _           => "RESERVED"
+        }
+    }
+}
+
+/**
+ * Gets the name of the graph or temporal structure or message matching the
+ * specified struct `signature`.
+ */
+pub fn signature_name(signature: u8) -> Option<&'static str> {
+    match signature {
+        STRUCT_NODE                 => Some("Node"),
+        STRUCT_RELATIONSHIP         => Some("Relationship"),
+        STRUCT_PATH                 => Some("Path"),
+        STRUCT_UNBOUND_RELATIONSHIP => Some("UnboundRelationship"),
+        STRUCT_DATE                 => Some("Date"),
+        STRUCT_TIME                 => Some("Time"),
+        STRUCT_LOCAL_TIME           => Some("LocalTime"),
+        STRUCT_DATE_TIME            => Some("DateTime"),
+        STRUCT_DATE_TIME_ZONE_ID    => Some("DateTimeZoneId"),
+        STRUCT_LEGACY_DATE_TIME     => Some("LegacyDateTime"),
+        STRUCT_LEGACY_DATE_TIME_ZONE_ID => Some("LegacyDateTimeZoneId"),
+        STRUCT_LOCAL_DATE_TIME      => Some("LocalDateTime"),
+        STRUCT_DURATION             => Some("Duration"),
+        STRUCT_POINT_2D             => Some("Point2D"),
+        STRUCT_POINT_3D             => Some("Point3D"),
+        MSG_INIT                    => Some("Init"),
+        MSG_RUN                     => Some("Run"),
+        MSG_DISCARD_ALL             => Some("DiscardAll"),
+        MSG_PULL_ALL                => Some("PullAll"),
+        MSG_ACK_FAILURE             => Some("AckFailure"),
+        MSG_RESET                   => Some("Reset"),
+        MSG_RECORD                  => Some("Record"),
+        MSG_SUCCESS                 => Some("Success"),
+        MSG_FAILURE                 => Some("Failure"),
+        MSG_IGNORED                 => Some("Ignored"),
+        _                           => None
+    }
+}
+
+fn hex(buf: &[u8], e: &Entry) -> String {
+    let size = e.header.len + e.header.bytes;
+    let shown = size.min(MAX_LINE_BYTES.max(e.header.len));
+    let bytes = buf[e.offset..e.offset + shown]
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if shown < size { format!("{} (+{})", bytes, size - shown) }
+    else { bytes }
+}
+
+fn describe(h: &Header, payload: &[u8]) -> String {
+    let payload = &payload[..h.bytes];
+    match h.kind {
+        PackType::Null => "null".into(),
+        PackType::Boolean => (h.marker == TRUE).to_string(),
+        PackType::Int => match h.bytes {
+            0 => (h.marker as i8).to_string(),
+            _ => payload
+                .iter()
+                .skip(1)
+                .fold(payload[0] as i8 as i64, |n, b| n << 8 | *b as i64)
+                .to_string()
+        },
+        PackType::Float => {
+            let bits = payload
+                .iter()
+                .fold(0u64, |n, b| n << 8 | *b as u64);
+            format!("{:?}", f64::from_bits(bits))
+        },
+        PackType::String => {
+            let cs = String::from_utf8_lossy(payload);
+            let shown = cs.chars().take(MAX_STRING_CHARS).collect::<String>();
+            let ellipsis = if shown.len() < cs.len() { "..." } else { "" };
+            format!("size={} {:?}{}", h.size, shown, ellipsis)
+        },
+        PackType::Struct => {
+            let signature = h.signature.unwrap_or_default();
+            match signature_name(signature) {
+                Some(name) => format!("size={} signature=0x{:02X} {}",
+                                      h.size, signature, name),
+                None => format!("size={} signature=0x{:02X}",
+                                h.size, signature)
+            }
+        },
+        _ if h.is_stream() => "size=?".into(),
+        PackType::Bytes
+        | PackType::List
+        | PackType::Map => format!("size={}", h.size),
+        PackType::EndOfStream | PackType::Reserved => String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(d: &Disassembly) -> Vec<(usize, usize, &'static str)> {
+        d.entries
+            .iter()
+            .map(|e| (e.offset, e.depth, marker_name(e.header.marker)))
+            .collect()
+    }
+
+    #[test]
+    fn disassembles_nested_values() {
+        let buf = [
+            0xB3, 0x4E,
+            0x01,
+            0x91, 0x84, b't', b'o', b't', b'o',
+            0xA1, 0x81, b'k', 0xC9, 0x01, 0x00,
+            0xC3
+        ];
+        let d = disassemble(&buf);
+        assert!(d.error.is_none());
+        assert_eq!(layout(&d), [
+            (0, 0, "TINY_STRUCT"),
+            (2, 1, "TINY_INT"),
+            (3, 1, "TINY_LIST"),
+            (4, 2, "TINY_STRING"),
+            (9, 1, "TINY_MAP"),
+            (10, 2, "TINY_STRING"),
+            (12, 2, "INT_16"),
+            (15, 0, "TRUE")
+        ]);
+        assert_eq!(d.to_string(), "\
+0000  B3 4E               TINY_STRUCT   size=3 signature=0x4E Node
+0002    01                TINY_INT      1
+0003    91                TINY_LIST     size=1
+0004      84 74 6F 74 6F  TINY_STRING   size=4 \"toto\"
+0009    A1                TINY_MAP      size=1
+000A      81 6B           TINY_STRING   size=1 \"k\"
+000C      C9 01 00        INT_16        256
+000F  C3                  TRUE          true
+");
+    }
+
+    #[test]
+    fn disassembles_streams() {
+        let d = disassemble(&[0xD7, 0x01, 0xDB, 0x81, b'k', 0x2A, 0xDF, 0xDF, 0x02]);
+        assert!(d.error.is_none());
+        assert_eq!(layout(&d), [
+            (0, 0, "LIST_STREAM"),
+            (1, 1, "TINY_INT"),
+            (2, 1, "MAP_STREAM"),
+            (3, 2, "TINY_STRING"),
+            (5, 2, "TINY_INT"),
+            (6, 1, "END_OF_STREAM"),
+            (7, 0, "END_OF_STREAM"),
+            (8, 0, "TINY_INT")
+        ]);
+        assert_eq!(d.to_string(), "\
+0000  D7         LIST_STREAM   size=?
+0001    01       TINY_INT      1
+0002    DB       MAP_STREAM    size=?
+0003      81 6B  TINY_STRING   size=1 \"k\"
+0005      2A     TINY_INT      42
+0006    DF       END_OF_STREAM
+0007  DF         END_OF_STREAM
+0008  02         TINY_INT      2
+");
+    }
+
+    #[test]
+    fn rejects_unexpected_end_of_stream() {
+        let d = disassemble(&[0xD7, 0x01, 0xDF, 0xDF]);
+        assert_eq!(layout(&d).len(), 3);
+        assert!(matches!(d.error, Some((3, BoltError::UnpackUnexpectedEndOfStream))));
+
+        let d = disassemble(&[0x92, 0x01, 0xDF]);
+        assert!(matches!(d.error, Some((2, BoltError::UnpackUnexpectedEndOfStream))));
+    }
+
+    #[test]
+    fn reports_truncated_input() {
+        let d = disassemble(&[0x92, 0x01]);
+        assert_eq!(layout(&d), [(0, 0, "TINY_LIST"), (1, 1, "TINY_INT")]);
+        assert!(matches!(d.error, Some((2, BoltError::UnpackUnexpectedEndOfStream))));
+
+        let d = disassemble(&[0x01, 0x84, b't', b'o']);
+        assert!(matches!(d.error, Some((1, BoltError::UnpackUnexpectedEndOfStream))));
+        assert!(d.to_string().ends_with("0001  error: UnpackUnexpectedEndOfStream\n"));
+
+        let d = disassemble(&[0xD7, 0x01]);
+        assert!(matches!(d.error, Some((2, BoltError::UnpackUnexpectedEndOfStream))));
+    }
+
+    #[test]
+    fn reports_reserved_markers() {
+        let d = disassemble(&[0x01, 0xC4]);
+        assert_eq!(layout(&d).len(), 1);
+        assert!(matches!(d.error, Some((1, BoltError::UnpackReservedMarker))));
+        assert_eq!(marker_name(0xC4), "RESERVED");
+        assert_eq!(marker_name(0xE0), "RESERVED");
+    }
+
+    #[test]
+    fn names_markers_and_signatures() {
+        assert_eq!(marker_name(0x7F), "TINY_INT");
+        assert_eq!(marker_name(0xF0), "TINY_INT");
+        assert_eq!(marker_name(0x8F), "TINY_STRING");
+        assert_eq!(marker_name(STRUCT_16), "STRUCT_16");
+        assert_eq!(signature_name(STRUCT_NODE), Some("Node"));
+        assert_eq!(signature_name(MSG_SUCCESS), Some("Success"));
+        assert_eq!(signature_name(0x00), None);
+    }
+}
@@ -17,13 +17,51 @@ pub enum BoltError {
     UnpackInvalidStructSize,
     UnpackInvalidBooleanMarker,
     UnpackInvalidIntMarker,
+    UnpackReservedMarker,
+    UnpackUnexpectedEndOfStream,
+    UnpackUnexpectedStructSignature { expected: u8, found: u8 },
+    UnpackStructFieldCountMismatch { min: usize, max: usize, found: usize },
+    UnknownEnumVariantSignature,
     PackHeaderSizeOverflow,
-    PackStructHeaderSizeOverflow
+    PackStructHeaderSizeOverflow,
+    PackUnregisteredStructSignature,
+    PackCustomStructTypeMismatch,
+    PackUnsupportedStructSignature { signature: u8 },
+    RegisterBuiltInStructSignature { signature: u8 },
+    PackReaderIoFailure(std::io::Error),
+    PackReaderBufferOverflow,
+    PackReaderUnexpectedEof,
+    InvalidVersion
 }
 
-impl std::error::Error for BoltError {}
+impl std::error::Error for BoltError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BoltError::PackReaderIoFailure(e) => Some(e),
+            _ => None
+        }
+    }
+}
 impl std::fmt::Display for BoltError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Unpacker error")
+        match self {
+            BoltError::UnpackUnexpectedStructSignature { expected, found } =>
+                write!(f, "Unpacker error: expected struct signature 0x{:02X}, found 0x{:02X}",
+                       expected, found),
+            BoltError::UnpackStructFieldCountMismatch { min, max, found } if min == max =>
+                write!(f, "Unpacker error: expected {} struct fields, found {}",
+                       max, found),
+            BoltError::UnpackStructFieldCountMismatch { min, max, found } =>
+                write!(f, "Unpacker error: expected {} to {} struct fields, found {}",
+                       min, max, found),
+            BoltError::PackUnsupportedStructSignature { signature } =>
+                write!(f, "Packer error: struct signature 0x{:02X} is not supported by the protocol",
+                       signature),
+            BoltError::RegisterBuiltInStructSignature { signature } =>
+                write!(f, "Registry error: struct signature 0x{:02X} is built in",
+                       signature),
+            BoltError::PackReaderIoFailure(e) => write!(f, "PackReader error: {}", e),
+            _ => write!(f, "Unpacker error")
+        }
     }
 }
@@ -3,3 +3,9 @@ pub mod types;
 pub mod error;
 pub mod packer;
 pub mod unpacker;
+pub mod structure;
+pub mod version;
+pub mod scan;
+#[cfg(feature = "tokio")]
+pub mod reader;
+pub mod disasm;
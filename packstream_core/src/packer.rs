@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::ops::{Index,Range,RangeTo,RangeFrom,RangeFull};
 use crate::consts::*;
 use crate::error::BoltError;
+use crate::version::Version;
 
 pub struct PackOutput {
     pub buf: Vec<u8>
@@ -25,16 +26,53 @@ impl PackOutput {
 }
 
 pub struct Packer {
-    pub out: PackOutput
+    pub out: PackOutput,
+    version: Version,
+    utc_patch: bool,
 }
 
 impl Packer {
     pub fn new() -> Self {
+        Packer::with_version(Version::default())
+    }
+
+    /**
+     * Creates a packer writing structures with their layout in the
+     * specified protocol `version`.
+     */
+    pub fn with_version(version: Version) -> Self {
         Packer {
-            out: PackOutput::new()
+            out: PackOutput::new(),
+            version,
+            utc_patch: false,
         }
     }
 
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    /// Whether the server accepted the `utc` patch of Bolt 4.3 and 4.4.
+    pub fn utc_patch(&self) -> bool {
+        self.utc_patch
+    }
+
+    pub fn set_utc_patch(&mut self, utc_patch: bool) {
+        self.utc_patch = utc_patch;
+    }
+
+    /**
+     * Whether date times are packed with their UTC based encoding, as of
+     * Bolt 5 or with the `utc` patch, rather than their legacy one.
+     */
+    pub fn utc_date_times(&self) -> bool {
+        self.utc_patch || self.version >= Version::V5_0
+    }
+
     pub fn len(&mut self) -> usize {
         self.out.buf.len()
     }
@@ -84,6 +122,13 @@ impl Packer {
         }
     }
 
+    /**
+     * Pushes null to the output stream.
+     */
+    pub fn pack_null(&mut self) -> Result<(), BoltError> {
+        self.out.push_byte(NULL)
+    }
+
     /**
      */
     pub fn pack_struct_header(
@@ -112,14 +157,13 @@ pub trait PackValue<T> {
     fn pack(&mut self, val: T) -> Result<(), BoltError>;
 }
 
+/// Pushes the value if any, null otherwise.
 impl<T> PackValue<Option<T>> for Packer
     where Packer: PackValue<T> {
     fn pack(&mut self, val: Option<T>) -> Result<(), BoltError> {
-        if val.is_none() {
-            self.out.push_byte(NULL)
-        }
-        else {
-            self.pack(val.unwrap())
+        match val {
+            Some(x) => self.pack(x),
+            None => self.pack_null()
         }
     }
 }
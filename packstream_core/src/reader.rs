@@ -0,0 +1,198 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+use crate::error::BoltError;
+use crate::scan::Scanner;
+use crate::unpacker::{Unpacker, UnpackValue};
+use crate::version::Version;
+
+/// Number of bytes requested from the underlying reader at once.
+const READ_CHUNK: usize = 8 * 1024;
+/// Default upper bound of bytes buffered for a single value.
+pub const DEFAULT_LIMIT: usize = 16 * 1024 * 1024;
+
+/**
+ * Decodes PackStream values from an [AsyncRead](tokio::io::AsyncRead).
+ *
+ * Bytes are buffered until a whole value is available, then handed to an
+ * [Unpacker](crate::unpacker::Unpacker). The buffer never grows past the
+ * configured limit: a value larger than the limit fails with
+ * [PackReaderBufferOverflow](BoltError::PackReaderBufferOverflow).
+ * Bytes following a decoded value are kept for the next call.
+ */
+pub struct PackReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    /// Progress of the measure of the value at the start of `buf`.
+    scanner: Scanner,
+    limit: usize,
+    version: Version,
+}
+
+impl<R> PackReader<R>
+    where R: AsyncRead + Unpin {
+    pub fn new(inner: R) -> Self {
+        PackReader::with_limit(inner, DEFAULT_LIMIT)
+    }
+
+    pub fn with_limit(inner: R, limit: usize) -> Self {
+        PackReader {
+            inner,
+            buf: Vec::new(),
+            scanner: Scanner::new(),
+            limit,
+            version: Version::default(),
+        }
+    }
+
+    /**
+     * Reads the next value. Returns `None` when the reader reached its end
+     * between two values; an end of input in the middle of a value fails with
+     * [PackReaderUnexpectedEof](BoltError::PackReaderUnexpectedEof).
+     */
+    pub async fn read<T>(&mut self) -> Result<Option<T>, BoltError>
+        where Unpacker: UnpackValue<T> {
+        loop {
+            if let Some(size) = self.scanner.measure(&self.buf)? {
+                self.scanner = Scanner::new();
+                let rest = self.buf.split_off(size);
+                let bytes = std::mem::replace(&mut self.buf, rest);
+                return Unpacker::with_version(bytes, self.version).unpack().map(Some);
+            }
+            if self.fill().await? == 0 {
+                return if self.buf.is_empty() { Ok(None) }
+                       else { Err(BoltError::PackReaderUnexpectedEof) };
+            }
+        }
+    }
+
+    /// Protocol version of the structures read.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Sets the protocol version of the following structures, usually once
+    /// negotiated with the server.
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    /// Bytes read from the underlying reader but not decoded yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[..]
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    async fn fill(&mut self) -> Result<usize, BoltError> {
+        let start = self.buf.len();
+        let size = READ_CHUNK.min(self.limit.saturating_sub(start));
+        if size == 0 {
+            return Err(BoltError::PackReaderBufferOverflow);
+        }
+        self.buf.resize(start + size, 0);
+        let read = self.inner.read(&mut self.buf[start..]).await;
+        let n = *read.as_ref().unwrap_or(&0);
+        self.buf.truncate(start + n);
+        read.map_err(BoltError::PackReaderIoFailure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+    use crate::packer::{Packer, PackValue};
+
+    /// Reader handing out its bytes `chunk` at a time, then failing with
+    /// `error` if any.
+    struct Chunked {
+        bytes: Vec<u8>,
+        chunk: usize,
+        error: Option<io::ErrorKind>,
+    }
+
+    impl AsyncRead for Chunked {
+        fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut ReadBuf<'_>)
+                     -> Poll<io::Result<()>> {
+            if self.bytes.is_empty() {
+                if let Some(kind) = self.error.take() {
+                    return Poll::Ready(Err(kind.into()));
+                }
+            }
+            let n = self.chunk.min(self.bytes.len()).min(buf.remaining());
+            buf.put_slice(&self.bytes[..n]);
+            self.bytes.drain(..n);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn chunked(bytes: Vec<u8>, chunk: usize) -> Chunked {
+        Chunked { bytes, chunk, error: None }
+    }
+
+    fn packed(values: &[&str]) -> Vec<u8> {
+        let mut packer = Packer::new();
+        for value in values {
+            packer.pack(vec![value.to_string(); 3]).unwrap();
+        }
+        packer.out.buf
+    }
+
+    #[tokio::test]
+    async fn reads_consecutive_values_then_none() {
+        let mut reader = PackReader::new(chunked(packed(&["a", "bc"]), READ_CHUNK));
+        assert_eq!(reader.read().await.unwrap(), Some(vec!["a".to_string(); 3]));
+        assert_eq!(reader.read().await.unwrap(), Some(vec!["bc".to_string(); 3]));
+        assert_eq!(reader.read::<Vec<String>>().await.unwrap(), None);
+        assert!(reader.buffer().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reads_values_split_across_reads() {
+        let long = "x".repeat(3 * READ_CHUNK);
+        let mut reader = PackReader::new(chunked(packed(&["a", &long, "b"]), 7));
+        assert_eq!(reader.read().await.unwrap(), Some(vec!["a".to_string(); 3]));
+        assert_eq!(reader.read().await.unwrap(), Some(vec![long; 3]));
+        assert_eq!(reader.read().await.unwrap(), Some(vec!["b".to_string(); 3]));
+        assert_eq!(reader.read::<Vec<String>>().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn fails_on_end_of_input_within_a_value() {
+        let mut bytes = packed(&["a", "bc"]);
+        bytes.pop();
+        let mut reader = PackReader::new(chunked(bytes, 2));
+        assert!(reader.read::<Vec<String>>().await.unwrap().is_some());
+        assert!(matches!(reader.read::<Vec<String>>().await,
+                         Err(BoltError::PackReaderUnexpectedEof)));
+    }
+
+    #[tokio::test]
+    async fn fails_on_value_larger_than_limit() {
+        let mut reader = PackReader::with_limit(chunked(packed(&["abcdef"]), 4), 8);
+        assert!(matches!(reader.read::<Vec<String>>().await,
+                         Err(BoltError::PackReaderBufferOverflow)));
+    }
+
+    #[tokio::test]
+    async fn keeps_io_errors() {
+        let reader = Chunked {
+            bytes: vec![0x91],
+            chunk: 1,
+            error: Some(io::ErrorKind::ConnectionReset)
+        };
+        match PackReader::new(reader).read::<Vec<i64>>().await {
+            Err(BoltError::PackReaderIoFailure(e)) =>
+                assert_eq!(e.kind(), io::ErrorKind::ConnectionReset),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+}
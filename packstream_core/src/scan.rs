@@ -0,0 +1,226 @@
+use crate::consts::*;
+use crate::error::BoltError;
+use crate::types::PackType;
+
+/**
+ * Decoded marker of a PackStream value, read without consuming the value
+ * itself.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    /// Marker byte of the value.
+    pub marker: u8,
+    /// Primitive type of the value.
+    pub kind: PackType,
+    /// Number of bytes used by the header, including the marker, the size
+    /// bytes and the struct signature.
+    pub len: usize,
+    /// Number of raw bytes following the header (ints, floats, strings and
+    /// bytes). Zero for containers.
+    pub bytes: usize,
+    /// Decoded size: string or byte length, list items, map entries or
+    /// struct fields. Zero for streams and scalars.
+    pub size: usize,
+    /// Struct signature, when the value is a struct.
+    pub signature: Option<u8>,
+}
+
+impl Header {
+    /// Number of values nested into this one (map entries count twice).
+    pub fn items(&self) -> usize {
+        match self.kind {
+            PackType::List | PackType::Struct => self.size,
+            PackType::Map => self.size * 2,
+            _ => 0
+        }
+    }
+
+    /// Whether the value is a list or map terminated by `END_OF_STREAM`.
+    pub fn is_stream(&self) -> bool {
+        self.marker == LIST_STREAM || self.marker == MAP_STREAM
+    }
+}
+
+/**
+ * Reads the header of the value starting at the beginning of `buf`.
+ * Returns `None` when `buf` is too short to hold the whole header.
+ */
+pub fn header(buf: &[u8]) -> Result<Option<Header>, BoltError> {
+    let marker = match buf.first() {
+        Some(byte) => *byte,
+        None => return Ok(None)
+    };
+    let kind = PackType::from(marker);
+    let fixed = |bytes: usize| Ok(Some(Header {
+        marker, kind, len: 1, bytes, size: 0, signature: None
+    }));
+    let sized = |width: usize| Ok(read_size(&buf[1..], width)
+        .map(|size| Header {
+            marker,
+            kind,
+            len: 1 + width,
+            bytes: match kind {
+                PackType::String | PackType::Bytes => size,
+                _ => 0
+            },
+            size,
+            signature: None
+        }));
+
+    let header = match marker {
+        NULL | FALSE | TRUE | END_OF_STREAM => fixed(0),
+        TINY_INT_PF..=TINY_INT_PL | TINY_INT_NF..=TINY_INT_NL => fixed(0),
+        INT_8 => fixed(1),
+        INT_16 => fixed(2),
+        INT_32 => fixed(4),
+        INT_64 | FLOAT_64 => fixed(8),
+        LIST_STREAM | MAP_STREAM => fixed(0),
+        BYTES_8 | STRING_8 | LIST_8 | MAP_8 | STRUCT_8 => sized(1),
+        BYTES_16 | STRING_16 | LIST_16 | MAP_16 | STRUCT_16 => sized(2),
+        BYTES_32 | STRING_32 | LIST_32 | MAP_32 => sized(4),
+        b if b & HIGH_NIBBLE == TINY_STRING => {
+            let size = (b & LOW_NIBBLE) as usize;
+            Ok(Some(Header {
+                marker, kind, len: 1, bytes: size, size, signature: None
+            }))
+        },
+        b if b & HIGH_NIBBLE == TINY_LIST
+            || b & HIGH_NIBBLE == TINY_MAP
+            || b & HIGH_NIBBLE == TINY_STRUCT => Ok(Some(Header {
+                marker,
+                kind,
+                len: 1,
+                bytes: 0,
+                size: (b & LOW_NIBBLE) as usize,
+                signature: None
+            })),
+        _ => Err(BoltError::UnpackReservedMarker)
+    }?;
+
+    match header {
+        Some(h) if h.kind == PackType::Struct => Ok(buf
+            .get(h.len)
+            .map(|sign| Header { len: h.len + 1, signature: Some(*sign), ..h })),
+        h => Ok(h)
+    }
+}
+
+/**
+ * Computes the number of bytes used by the value starting at the beginning
+ * of `buf`, nested values included. Returns `None` when `buf` does not hold
+ * the whole value yet.
+ */
+pub fn measure(buf: &[u8]) -> Result<Option<usize>, BoltError> {
+    Scanner::new().measure(buf)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pending { Items(usize), Stream }
+
+/**
+ * Resumable form of [measure] for a buffer that grows between calls. The
+ * values already measured are not scanned again, so that measuring a large
+ * value read in chunks stays linear. Each call must be given the bytes of
+ * the previous one followed by the new ones.
+ */
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    offset: usize,
+    stack: Vec<Pending>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner::new()
+    }
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Scanner { offset: 0, stack: vec![Pending::Items(1)] }
+    }
+
+    /// Size of the value at the beginning of `buf`, `None` while it is
+    /// incomplete.
+    pub fn measure(&mut self, buf: &[u8]) -> Result<Option<usize>, BoltError> {
+        loop {
+            match self.stack.last() {
+                None => return Ok(Some(self.offset)),
+                Some(Pending::Items(0)) => {
+                    self.stack.pop();
+                    continue;
+                },
+                Some(Pending::Items(_)) => (),
+                Some(Pending::Stream) => match buf.get(self.offset) {
+                    None => return Ok(None),
+                    Some(&END_OF_STREAM) => {
+                        self.offset += 1;
+                        self.stack.pop();
+                        continue;
+                    },
+                    Some(_) => ()
+                }
+            }
+
+            let h = match header(&buf[self.offset..])? {
+                Some(h) => h,
+                None => return Ok(None)
+            };
+            if h.marker == END_OF_STREAM {
+                return Err(BoltError::UnpackUnexpectedEndOfStream);
+            }
+            let end = self.offset + h.len + h.bytes;
+            if end > buf.len() {
+                return Ok(None);
+            }
+            // The value is complete: only now is the progress recorded.
+            if let Some(Pending::Items(n)) = self.stack.last_mut() {
+                *n -= 1;
+            }
+            self.offset = end;
+            if h.is_stream() {
+                self.stack.push(Pending::Stream);
+            }
+            else if h.items() > 0 {
+                self.stack.push(Pending::Items(h.items()));
+            }
+        }
+    }
+}
+
+fn read_size(buf: &[u8], width: usize) -> Option<usize> {
+    buf.get(..width).map(|bytes| bytes
+        .iter()
+        .fold(0, |size, b| size << 8 | *b as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// List of a string, a map with a nested list stream, and a struct.
+    const VALUE: [u8; 17] = [
+        0x93,
+        0x82, b'a', b'b',
+        0xA1, 0x81, b'k', 0xD7, 0x01, 0x02, 0xDF,
+        0xB2, 0x4E, 0x01, 0xC0,
+        0x00, 0x00
+    ];
+
+    #[test]
+    fn measures_nested_values() {
+        assert_eq!(measure(&VALUE).unwrap(), Some(15));
+        assert_eq!(measure(&VALUE[..14]).unwrap(), None);
+        assert_eq!(measure(&[]).unwrap(), None);
+        assert!(matches!(measure(&[0xDF]), Err(BoltError::UnpackUnexpectedEndOfStream)));
+    }
+
+    #[test]
+    fn resumes_on_growing_buffer() {
+        let mut scanner = Scanner::new();
+        for len in 0..15 {
+            assert_eq!(scanner.measure(&VALUE[..len]).unwrap(), None, "at {}", len);
+        }
+        assert_eq!(scanner.measure(&VALUE).unwrap(), Some(15));
+    }
+}
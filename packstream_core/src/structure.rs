@@ -0,0 +1,54 @@
+use crate::version::{Version, VersionRange};
+
+/**
+ * Layout of a type packed as a PackStream structure, implemented by the
+ * `bolt_packstream` attribute and the `BoltStruct` derive.
+ *
+ * Unlike the inherent `struct_sign()` and `struct_size()` functions generated
+ * along with it, this trait lets generic code reach the layout of any
+ * structure type:
+ *
+ * ```ignore
+ * fn describe<T: BoltStructure>() -> String {
+ *     format!("0x{:02X} ({})", T::SIGNATURE, T::FIELDS.join(", "))
+ * }
+ * ```
+ */
+pub trait BoltStructure {
+    /// Signature byte written after the structure header.
+    const SIGNATURE: u8;
+    /// Names of the packed fields, in packing order. Fields of a tuple struct
+    /// are named after their index. Fields of every protocol version are
+    /// listed, see [fields_at](BoltStructure::fields_at).
+    const FIELDS: &'static [&'static str];
+    /// Protocol versions each of [FIELDS](BoltStructure::FIELDS) is packed in.
+    const VERSIONS: &'static [VersionRange];
+    /// Number of fields over every protocol version, as listed by
+    /// [FIELDS](BoltStructure::FIELDS). A single version may pack fewer, see
+    /// [size_at](BoltStructure::size_at).
+    const SIZE: usize = Self::FIELDS.len();
+    /// Number of fields required to unpack the structure, trailing optional
+    /// fields excluded, in every protocol version.
+    const MIN_SIZE: usize;
+    /// Whether fields packed past the last declared one are skipped when
+    /// unpacking instead of failing.
+    const SKIP_EXTRA: bool;
+
+    /// Names of the fields packed in the specified protocol `version`.
+    fn fields_at(version: Version) -> Vec<&'static str> {
+        Self::FIELDS
+            .iter()
+            .zip(Self::VERSIONS)
+            .filter(|(_, range)| range.contains(version))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Number of fields packed in the specified protocol `version`.
+    fn size_at(version: Version) -> usize {
+        Self::VERSIONS
+            .iter()
+            .filter(|range| range.contains(version))
+            .count()
+    }
+}
@@ -5,6 +5,7 @@ use crate::consts::*;
  * primitives of the Neo4j type system. Graph primitives and rich composites
  * are represented as [Struct](PackType::Struct).
  */
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PackType {
     Null,
     Boolean,
@@ -33,6 +34,8 @@ impl PackType {
             TINY_STRUCT..=0xBF => PackType::Struct,
             _ => if marker >= (TINY_MIN as u8) { PackType::Int } else {
                 match marker {
+                    /* NULL **************************************************/
+                    NULL => PackType::Null,
                     /* BOOLEAN ***********************************************/
                       FALSE
                     | TRUE => PackType::Boolean,
@@ -1,5 +1,7 @@
 use crate::consts::*;
 use crate::error::BoltError;
+use crate::scan::measure;
+use crate::version::Version;
 use std::collections::HashMap;
 
 pub struct PackInput {
@@ -108,15 +110,33 @@ impl PackInput {
 
 pub struct Unpacker {
     pub input: PackInput,
+    version: Version,
 }
 
 impl Unpacker {
     pub fn new(buf: Vec<u8>) -> Self {
+        Unpacker::with_version(buf, Version::default())
+    }
+
+    /**
+     * Creates an unpacker expecting structures with their layout in the
+     * specified protocol `version`.
+     */
+    pub fn with_version(buf: Vec<u8>, version: Version) -> Self {
         Unpacker {
-            input: PackInput::new(buf)
+            input: PackInput::new(buf),
+            version,
         }
     }
 
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
     pub fn len(self) -> usize {
         self.input.len()
     }
@@ -272,6 +292,14 @@ impl Unpacker {
     pub fn unpack_struct_signature(&mut self) -> Result<u8, BoltError> {
         self.input.read_u8()
     }
+
+    /// Skips the next value, nested values included.
+    pub fn skip_value(&mut self) -> Result<(), BoltError> {
+        let rest = self.input.buf.get(self.input.index..).unwrap_or(&[]);
+        measure(rest)?
+            .ok_or(BoltError::PackInputReadInvalidSliceRange)
+            .and_then(|size| self.input.read_slice(size).map(|_| ()))
+    }
 }
 
 pub trait UnpackValue<T> {
@@ -346,6 +374,19 @@ impl UnpackValue<String> for Unpacker {
     }
 }
 
+/// Reads null as `None`, any other value as `Some`.
+impl<T> UnpackValue<Option<T>> for Unpacker
+    where Unpacker: UnpackValue<T> {
+    fn unpack(&mut self) -> Result<Option<T>, BoltError> {
+        if self.input.peek()? == NULL {
+            self.input.read_u8().map(|_| None)
+        }
+        else {
+            self.unpack().map(Some)
+        }
+    }
+}
+
 impl<T> UnpackValue<Vec<T>> for Unpacker
     where Unpacker: UnpackValue<T> {
     fn unpack(&mut self) -> Result<Vec<T>, BoltError> {
@@ -378,3 +419,38 @@ fn insert<K, V>(kvs: &mut HashMap<K, V>, key: K, value: V)
     where K: std::cmp::Eq, K: std::hash::Hash {
     kvs.insert(key, value);
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::packer::{Packer, PackValue};
+    use super::*;
+
+    fn round_trip<T>(value: T) -> (Vec<u8>, T)
+        where Packer: PackValue<T>, Unpacker: UnpackValue<T> {
+        let mut packer = Packer::new();
+        packer.pack(value).unwrap();
+        let bytes = packer[..].to_vec();
+        let mut unpacker = Unpacker::new(bytes.clone());
+        let value = unpacker.unpack().unwrap();
+        assert!(unpacker.input.peek().is_err(), "unread bytes after {:02X?}", bytes);
+        (bytes, value)
+    }
+
+    #[test]
+    fn round_trips_options() {
+        assert_eq!(round_trip(None::<i64>), (vec![NULL], None));
+        assert_eq!(round_trip(Some(42i64)), (vec![42], Some(42)));
+        assert_eq!(round_trip(Some("a".to_owned())).1, Some("a".to_owned()));
+    }
+
+    #[test]
+    fn round_trips_nested_nulls() {
+        let list = vec![Some(1i64), None, Some(3)];
+        assert_eq!(round_trip(list.clone()).1, list);
+
+        let mut map = HashMap::new();
+        map.insert("a".to_owned(), None);
+        map.insert("b".to_owned(), Some(vec![None, Some(true)]));
+        assert_eq!(round_trip(map.clone()).1, map);
+    }
+}
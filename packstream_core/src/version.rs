@@ -0,0 +1,82 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::error::BoltError;
+
+/**
+ * Bolt protocol version negotiated with the server.
+ *
+ * Some structures change layout across protocol versions, the version
+ * carried by [Packer](crate::packer::Packer) and
+ * [Unpacker](crate::unpacker::Unpacker) selects which fields of such
+ * structures are packed and expected. It defaults to
+ * [V1_0](Version::V1_0), matching the layouts of the first protocol
+ * versions.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl Version {
+    pub const V1_0: Version = Version::new(1, 0);
+    pub const V4_4: Version = Version::new(4, 4);
+    pub const V5_0: Version = Version::new(5, 0);
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Version { major, minor }
+    }
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::V1_0
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/**
+ * Parses a `major.minor` version, the minor part defaulting to 0 when
+ * omitted.
+ */
+impl FromStr for Version {
+    type Err = BoltError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(2, '.').map(str::parse::<u8>);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(major)), None) => Ok(Version::new(major, 0)),
+            (Some(Ok(major)), Some(Ok(minor))) => Ok(Version::new(major, minor)),
+            _ => Err(BoltError::InvalidVersion)
+        }
+    }
+}
+
+/**
+ * Range of protocol versions a structure field is packed in, both bounds
+ * being included.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VersionRange {
+    pub since: Option<Version>,
+    pub until: Option<Version>,
+}
+
+impl VersionRange {
+    /// Range of a field packed in every version.
+    pub const ALL: VersionRange = VersionRange::new(None, None);
+
+    pub const fn new(since: Option<Version>, until: Option<Version>) -> Self {
+        VersionRange { since, until }
+    }
+
+    pub fn contains(&self, version: Version) -> bool {
+        self.since.iter().all(|&since| since <= version)
+            && self.until.iter().all(|&until| version <= until)
+    }
+}
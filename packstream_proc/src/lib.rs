@@ -2,91 +2,173 @@ extern crate syn;
 extern crate quote;
 extern crate proc_macro;
 
+use std::collections::HashSet;
 use proc_macro::TokenStream;
-use syn::export::{TokenStream2, Span};
-use syn::{ItemStruct, ItemEnum, LitInt, Field, Fields, Ident};
-use syn::{parse_macro_input};
-use quote::quote;
+use proc_macro2::{TokenStream as TokenStream2, Span};
+use syn::{Data, DeriveInput, ItemStruct, ItemEnum, LitInt, Field, Fields, Generics};
+use syn::{Ident, Lit, Meta, Path, Variant, WherePredicate};
+use syn::{parse_macro_input, parse_quote, NestedMeta};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use quote::{quote, format_ident};
 
-const ALPHA: &'static str = "abcdefghijklmnopqrstuvwxyz";
+/// Maximum number of fields of a struct, as written by
+/// `Packer::pack_struct_header`.
+const MAX_STRUCT_SIZE: usize = i16::MAX as usize;
 
-#[proc_macro_attribute]
-pub fn bolt_packstream(sign: TokenStream, input: TokenStream) -> TokenStream {
-    let input: ItemStruct = parse_macro_input!(input as ItemStruct);
-    let sign: LitInt = parse_macro_input!(sign as LitInt);
-    
-    // TODO returns a tuple here only. Should do one iteration only
-    let pack_calls = input.fields.iter().map(pack_call).collect::<Vec<_>>();
-    let field_names = input.fields.iter().map(field_name).collect::<Vec<_>>();
-    let field_values = input.fields.iter().map(field_value).collect::<Vec<_>>();
-    
-    let size = pack_calls.len();
-    let name = &input.ident;
+mod structure;
+mod map;
 
-    TokenStream::from(quote! {
-    #input
+use structure::{Structure, StructOptions};
 
-    impl #name {
-        pub fn struct_size() -> usize { #size }
-        pub fn struct_sign() -> u8 { #sign }
-    }
+/// Implements `PackValue` and `UnpackValue` for a struct packed as a
+/// PackStream structure of the given signature, its fields being packed in
+/// declaration order.
+///
+/// Unpacking fails when the signature or the number of fields does not match
+/// the struct, unless `skip_extra` is given: fields packed past the last
+/// declared one are then skipped, for forward compatibility. Fields accept
+/// the `#[bolt(...)]` attributes of [BoltStruct](derive@BoltStruct).
+///
+/// ```ignore
+/// #[bolt_packstream(0x4E)]
+/// pub struct Node {
+///     pub id: i64,
+///     pub labels: Vec<String>,
+///     pub properties: HashMap<String, Value>
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn bolt_packstream(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut input: ItemStruct = parse_macro_input!(input as ItemStruct);
+    let args = parse_macro_input!(args with Punctuated::<NestedMeta, Comma>::parse_terminated);
 
-    impl PackValue<#name> for Packer {
-        fn pack(&mut self, s:#name) -> Result<(), BoltError> {
-            self.pack_struct_header(#size, #sign)?;
-            #(#pack_calls)*
-            Ok(())
-        }
-    }
+    let impls = StructOptions::from_metas(args, &input.ident)
+        .and_then(|options| Structure::new(
+            &input.ident, &input.generics, options, &input.fields
+        ))
+        .map(|s| s.expand())
+        .unwrap_or_else(|e| e.to_compile_error());
 
-    impl UnpackValue<#name> for Unpacker {
-        fn unpack(&mut self) -> Result<#name, BoltError> {
-            self.unpack_struct_header()
-                .and_then(|_| self.unpack_struct_signature())
-                .and_then(|_| {
-                    #(#field_values)*
-                    Ok(#name {
-                        #(#field_names)*
-                    })
-                })
-        }
-    }
+    input.fields
+        .iter_mut()
+        .for_each(|field| field.attrs.retain(|a| !a.path.is_ident("bolt")));
+    TokenStream::from(quote! {
+    #input
+    #impls
   })
 }
 
-fn pack_call(f: &syn::Field) -> syn::export::TokenStream2 {
-    let field = &f.ident;
-    quote!(self.pack(s.#field)?;)
+/// Derive form of [bolt_packstream](macro@bolt_packstream), the signature
+/// being given by a `#[bolt(signature = 0x..)]` attribute, along with the
+/// optional `skip_extra` flag: `#[bolt(signature = 0x4E, skip_extra)]`.
+///
+/// Fields accept the following attributes:
+/// - `#[bolt(skip)]`: not packed, unpacked as `Default::default()`.
+/// - `#[bolt(default)]`: unpacked as `Default::default()` when null.
+/// - `#[bolt(optional)]`: trailing field which may be missing from the
+///   packed structure, unpacked as `Default::default()` in that case.
+/// - `#[bolt(with = "module")]`: packed with `module::pack(&mut Packer, T)`
+///   and unpacked with `module::unpack(&mut Unpacker)`.
+/// - `#[bolt(since = "5.0")]`, `#[bolt(until = "4.4")]`: only packed when
+///   the version of the `Packer` or `Unpacker` is in range, unpacked as
+///   `Default::default()` otherwise.
+///
+/// ```ignore
+/// #[derive(BoltStruct)]
+/// #[bolt(signature = 0x4E)]
+/// pub struct Node {
+///     pub id: i64,
+///     pub labels: Vec<String>,
+///     #[bolt(default)]
+///     pub properties: HashMap<String, Value>
+/// }
+/// ```
+#[proc_macro_derive(BoltStruct, attributes(bolt))]
+pub fn derive_bolt_struct(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return TokenStream::from(syn::Error::new_spanned(
+            &input.ident, "BoltStruct can only be derived for structs"
+        ).to_compile_error())
+    };
+
+    let impls = StructOptions::from_attrs(&input.attrs, &input.ident)
+        .and_then(|options| Structure::new(
+            &input.ident, &input.generics, options, fields
+        ))
+        .map(|s| s.expand())
+        .unwrap_or_else(|e| e.to_compile_error());
+    TokenStream::from(impls)
 }
 
-fn field_name(f: &syn::Field) -> syn::export::TokenStream2 {
-    let field = &f.ident;
-    quote!(#field,)
+/// Implements `packstream_v1::convert::FromMap` for a struct with named
+/// fields, each field being converted from the value of the key of the same
+/// name with `FromValue`. `FromValue` is implemented as well, from a map or
+/// the properties of a node or a relationship.
+///
+/// Fields accept the following attributes:
+/// - `#[map(rename = "key")]`: read from another key.
+/// - `#[map(default)]`: `Default::default()` when the key is missing or null.
+/// - `#[map(skip)]`: not read, always `Default::default()`.
+///
+/// Fields of type `Option<T>` are `None` when their key is missing or null,
+/// other missing keys fail with `ConversionError::MissingKey`.
+///
+/// The generated code refers to `::packstream_v1`. A crate depending on it
+/// under another name, or re-exporting it, gives its path with
+/// `#[map(crate = "path")]` on the struct.
+///
+/// ```ignore
+/// #[derive(FromMap)]
+/// pub struct Person {
+///     pub name: String,
+///     #[map(rename = "yearOfBirth")]
+///     pub born: i64,
+///     pub email: Option<String>
+/// }
+/// ```
+#[proc_macro_derive(FromMap, attributes(map))]
+pub fn derive_from_map(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+    TokenStream::from(map::expand_from_map(&input).unwrap_or_else(|e| e.to_compile_error()))
 }
 
-fn field_value(f: &syn::Field) -> syn::export::TokenStream2 {
-    let field = &f.ident;
-    quote!(let #field = self.unpack()?;)
+/// Implements `packstream_v1::convert::IntoMap` and `IntoValue` for a struct
+/// with named fields, each field being written to the key of the same name.
+/// Fields accept the `rename` and `skip` attributes of
+/// [FromMap](derive@FromMap), and the struct its `crate` attribute.
+#[proc_macro_derive(IntoMap, attributes(map))]
+pub fn derive_into_map(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+    TokenStream::from(map::expand_into_map(&input).unwrap_or_else(|e| e.to_compile_error()))
 }
 
-struct EnumContext {
-    variant_names: Vec<TokenStream2>,
-    variant_fields_names: Vec<TokenStream2>,
-    variant_fields_unpack: Vec<TokenStream2>,
-    variant_fields_encode: Vec<Vec<TokenStream2>>,
-    variant_fields_decode: Vec<Vec<TokenStream2>>,
+/// Implements `packstream_v1::row::FromRow` for a struct with named fields,
+/// each field being converted from the column of the same name. Fields and
+/// the struct accept the attributes of [FromMap](derive@FromMap) as
+/// `#[row(...)]`.
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// pub struct Count {
+///     pub label: String,
+///     #[row(rename = "count(n)")]
+///     pub count: i64
+/// }
+/// ```
+#[proc_macro_derive(FromRow, attributes(row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+    TokenStream::from(map::expand_from_row(&input).unwrap_or_else(|e| e.to_compile_error()))
 }
 
-impl EnumContext {
-    fn new() -> EnumContext {
-        EnumContext {
-            variant_names: vec![],
-            variant_fields_names: vec![],
-            variant_fields_unpack: vec![],
-            variant_fields_encode: vec![],
-            variant_fields_decode: vec![],
-        }
-    }
+/// Pack and unpack arms generated for a single enum variant.
+struct VariantContext {
+    sign: Option<u8>,
+    pack: TokenStream2,
+    unpack: Option<TokenStream2>,
 }
 
 struct VariantFieldsContext {
@@ -105,79 +187,221 @@ impl VariantFieldsContext {
     }
 }
 
+/// Implements `PackValue` and `UnpackValue` for an enum whose variants are
+/// PackStream structures.
+///
+/// A variant annotated with `#[signature(0x..)]` is a structure of its own:
+/// its fields are packed after a struct header carrying that signature.
+/// A variant without signature must wrap a single `bolt_packstream` type, and
+/// is packed and unpacked as that type.
+///
+/// ```ignore
+/// #[bolt_enum]
+/// pub enum Message {
+///     Init(Init),
+///     #[signature(0x2F)]
+///     DiscardAll,
+/// }
+/// ```
+///
+/// With `#[bolt_enum(fallback = "module")]`, structures of any other
+/// signature are unpacked with `module::unpack(&mut Unpacker, u8)` instead of
+/// failing, and variants annotated with `#[fallback]` are packed with
+/// `module::pack(&mut Packer, Enum)`. A fallback variant wrapping a single
+/// structure is still unpacked as that structure, which lets its encoding
+/// depend on the version of the `Packer`.
+///
+/// ```ignore
+/// #[bolt_enum(fallback = "unknown")]
+/// pub enum Struct {
+///     Node(Node),
+///     #[fallback]
+///     Unknown { signature: u8, fields: Vec<Value> }
+/// }
+/// ```
 #[proc_macro_attribute]
-pub fn bolt_enum(_sign: TokenStream, input: TokenStream) -> TokenStream {
-    let input: ItemEnum = parse_macro_input!(input as ItemEnum);
+pub fn bolt_enum(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut input: ItemEnum = parse_macro_input!(input as ItemEnum);
+    let args = parse_macro_input!(args with Punctuated::<NestedMeta, Comma>::parse_terminated);
     let enum_name = &input.ident;
-    let enum_generics = &input.generics;
-    let enum_context = input.variants
+
+    let fallback = match enum_fallback(args) {
+        Ok(fallback) => fallback,
+        Err(e) => return TokenStream::from(e.to_compile_error())
+    };
+    let mut signs = HashSet::new();
+    let variants = input.variants
         .iter()
-        .fold(EnumContext::new(), |mut ctx, variant| {
-            let variant_name = &variant.ident;
-            let variant_fields = &variant.fields;
-            let (
-                unpack,
-                encode,
-                decode
-            ) = match variant.fields {
-                Fields::Named(_) => named_fields_context(variant_fields),
-                Fields::Unnamed(_) => unnamed_fields_context(variant_fields),
-                Fields::Unit => (quote!(), vec![], vec![])
-            };
+        .map(|variant| variant_context(enum_name, variant, fallback.as_ref())
+            .and_then(|ctx| match ctx.sign {
+                Some(sign) if !signs.insert(sign) => Err(syn::Error::new_spanned(
+                    variant, format!("duplicate variant signature 0x{:02X}", sign)
+                )),
+                _ => Ok(ctx)
+            }))
+        .collect::<Result<Vec<_>, _>>();
+    let impls = variants
+        .map(|variants| enum_impls(enum_name, &input.generics, &variants, fallback.as_ref()))
+        .unwrap_or_else(|e| e.to_compile_error());
 
-            ctx.variant_names.push(quote!(#variant_name));
-            ctx.variant_fields_names.push(quote!(#variant_fields));
-            ctx.variant_fields_unpack.push(unpack);
-            ctx.variant_fields_encode.push(encode);
-            ctx.variant_fields_decode.push(decode);
-            ctx
-        });
+    input.variants
+        .iter_mut()
+        .for_each(|variant| variant.attrs.retain(|a| {
+            !a.path.is_ident("signature") && !a.path.is_ident("fallback")
+        }));
+    TokenStream::from(quote! {
+    #input
+    #impls
+  })
+}
+
+/// Reads the `fallback = "module"` argument of `bolt_enum`.
+fn enum_fallback<I>(args: I) -> Result<Option<Path>, syn::Error>
+    where I: IntoIterator<Item = NestedMeta> {
+    let mut fallback = None;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("fallback") =>
+                match nv.lit {
+                    Lit::Str(lit) => fallback = Some(lit.parse()?),
+                    lit => return Err(syn::Error::new_spanned(
+                        lit, "fallback must be a module path string"
+                    ))
+                },
+            arg => return Err(syn::Error::new_spanned(arg, "unknown bolt_enum argument"))
+        }
+    }
+    Ok(fallback)
+}
 
-    let variant_names = enum_context.variant_names;
-    let _fields_names = enum_context.variant_fields_names;
-    let unpack_fields = enum_context.variant_fields_unpack;
-    let encode_fields = enum_context.variant_fields_encode;
-    let decode_fields = enum_context.variant_fields_decode;
+fn enum_impls(enum_name: &Ident, generics: &Generics, variants: &[VariantContext],
+              fallback: Option<&Path>) -> TokenStream2 {
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let pack_generics = bounded(generics, |param| parse_quote!(
+        ::packstream_core::packer::Packer: ::packstream_core::packer::PackValue<#param>
+    ));
+    let (pack_impl_generics, _, pack_where_clause) = pack_generics.split_for_impl();
+    let unpack_generics = bounded(generics, |param| parse_quote!(
+        ::packstream_core::unpacker::Unpacker: ::packstream_core::unpacker::UnpackValue<#param>
+    ));
+    let (unpack_impl_generics, _, unpack_where_clause) = unpack_generics.split_for_impl();
+    let pack_arms = variants.iter().map(|v| &v.pack);
+    let unpack_arms = variants.iter().filter_map(|v| v.unpack.as_ref());
+    let unknown = match fallback {
+        Some(fallback) => quote!(signature => #fallback::unpack(self, signature)),
+        None => quote!(_ => ::std::result::Result::Err(
+            ::packstream_core::error::BoltError::UnknownEnumVariantSignature
+        ))
+    };
 
-    let stream = TokenStream::from(quote! {
-    impl#enum_generics PackValue<#enum_name#enum_generics> for Packer {
-      fn pack(&mut self, s:#enum_name#enum_generics) -> Result<(), BoltError> {
+    quote! {
+    impl#pack_impl_generics ::packstream_core::packer::PackValue<#enum_name#ty_generics>
+    for ::packstream_core::packer::Packer #pack_where_clause {
+      fn pack(&mut self, s:#enum_name#ty_generics)
+              -> ::std::result::Result<(), ::packstream_core::error::BoltError> {
         match s {
-          #(#enum_name::#variant_names #unpack_fields => {
-            #(#encode_fields)*
-            Ok(())
-          }),*
+          #(#pack_arms),*
         }
       }
     }
 
-    impl#enum_generics UnpackValue<#enum_name#enum_generics> for Unpacker {
-      fn unpack(&mut self) -> Result<#enum_name#enum_generics, BoltError> {
+    impl#unpack_impl_generics ::packstream_core::unpacker::UnpackValue<#enum_name#ty_generics>
+    for ::packstream_core::unpacker::Unpacker #unpack_where_clause {
+      fn unpack(&mut self)
+                -> ::std::result::Result<#enum_name#ty_generics, ::packstream_core::error::BoltError> {
         self
-          .unpack_struct_header()
-          .and_then(|_| self.unpack_struct_signature())
-          .and_then(|signature| {
-            match signature {
-              #(_ => {
-                #(#decode_fields)*
-                Ok(#enum_name::#variant_names #unpack_fields)
-              }),*
-              _ => Err(BoltError::UnknowEnumVariantSignature)
-            }
-            // TODO:
-            //   - unpack each fields
-            //   - create enum variant from signature
-            //   - assign variant fields with unpacked values
-            //   - returns enum variant
+          .peek_struct_signature()
+          .and_then(|signature| match signature {
+            #(#unpack_arms,)*
+            #unknown
           })
       }
     }
-  });
+  }
+}
 
-    println!("{}", stream);
-    TokenStream::from(quote! {
-    #input
-  })
+fn variant_context(enum_name: &Ident, variant: &Variant, fallback: Option<&Path>)
+                   -> Result<VariantContext, syn::Error> {
+    let variant_name = &variant.ident;
+    let sign = variant.attrs
+        .iter()
+        .find(|a| a.path.is_ident("signature"))
+        .map(|a| a.parse_args::<LitInt>().and_then(signature))
+        .transpose()?;
+    let fallback_pack = match variant.attrs.iter().find(|a| a.path.is_ident("fallback")) {
+        Some(attr) => match (fallback, &sign) {
+            (Some(fallback), None) => Some(quote!(
+                s @ #enum_name::#variant_name { .. } => #fallback::pack(self, s)
+            )),
+            (None, _) => return Err(syn::Error::new_spanned(
+                attr, "#[fallback] variant requires #[bolt_enum(fallback = \"module\")]"
+            )),
+            (Some(_), Some(_)) => return Err(syn::Error::new_spanned(
+                attr, "#[fallback] variant cannot have a #[signature(..)] attribute"
+            ))
+        },
+        None => None
+    };
+
+    match (sign, &variant.fields) {
+        (None, Fields::Unnamed(fields)) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            Ok(VariantContext {
+                sign: None,
+                pack: fallback_pack.unwrap_or_else(|| quote!(#enum_name::#variant_name(x) =>
+                    <Self as ::packstream_core::packer::PackValue<#ty>>::pack(self, x))),
+                unpack: Some(quote!(
+                    s if s == <#ty as ::packstream_core::structure::BoltStructure>::SIGNATURE =>
+                    <Self as ::packstream_core::unpacker::UnpackValue<#ty>>::unpack(self)
+                        .map(#enum_name::#variant_name)))
+            })
+        },
+        (None, _) => match fallback_pack {
+            Some(pack) => Ok(VariantContext { sign: None, pack, unpack: None }),
+            None => Err(syn::Error::new_spanned(
+                variant,
+                "bolt_enum variant must wrap a single structure or have a #[signature(..)] attribute"
+            ))
+        },
+        (Some(sign), fields) => {
+            let (unpack, encode, decode) = match fields {
+                Fields::Named(_) => named_fields_context(fields),
+                Fields::Unnamed(_) => unnamed_fields_context(fields),
+                Fields::Unit => (quote!(), vec![], vec![])
+            };
+            let size = encode.len();
+            if size > MAX_STRUCT_SIZE {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    format!("a struct cannot pack more than {} fields", MAX_STRUCT_SIZE)
+                ));
+            }
+            let size_var = Ident::new("size", Span::mixed_site());
+            Ok(VariantContext {
+                sign: sign.base10_parse().ok(),
+                pack: quote!(#enum_name::#variant_name #unpack => {
+                    self.pack_struct_header(#size, #sign)?;
+                    #(#encode)*
+                    ::std::result::Result::Ok(())
+                }),
+                unpack: Some(quote!(#sign => {
+                    let #size_var = self.unpack_struct_header()? as usize;
+                    self.unpack_struct_signature()?;
+                    if #size_var != #size {
+                        return ::std::result::Result::Err(
+                            ::packstream_core::error::BoltError::UnpackStructFieldCountMismatch {
+                                min: #size,
+                                max: #size,
+                                found: #size_var
+                            }
+                        );
+                    }
+                    #(#decode)*
+                    ::std::result::Result::Ok(#enum_name::#variant_name #unpack)
+                }))
+            })
+        }
+    }
 }
 
 fn named_fields_context(variant_fields: &Fields)
@@ -198,33 +422,37 @@ fn unnamed_fields_context(variant_fields: &Fields)
     let VariantFieldsContext { unpack, encode, decode } = variant_fields
         .iter()
         .enumerate()
-        .fold(VariantFieldsContext::new(), |mut ctx, (i, _)| {
+        .fold(VariantFieldsContext::new(), |mut ctx, (i, field)| {
             ctx.unpack.push(unpack_unnamed_field(i));
-            ctx.encode.push(encode_unnamed_field(i));
-            ctx.decode.push(decode_unnamed_field(i));
+            ctx.encode.push(encode_unnamed_field(i, field));
+            ctx.decode.push(decode_unnamed_field(i, field));
             ctx
         });
     (quote!((#(#unpack),*)), encode, decode)
 }
 
-fn encode_named_field(f: &syn::Field) -> TokenStream2 {
+fn encode_named_field(f: &Field) -> TokenStream2 {
     let field = &f.ident;
-    quote!(self.pack(#field)?;)
+    let ty = &f.ty;
+    quote!(<Self as ::packstream_core::packer::PackValue<#ty>>::pack(self, #field)?;)
 }
 
-fn encode_unnamed_field(i: usize) -> TokenStream2 {
+fn encode_unnamed_field(i: usize, f: &Field) -> TokenStream2 {
     let ident = gen_ident(i);
-    quote!(self.pack(#ident)?;)
+    let ty = &f.ty;
+    quote!(<Self as ::packstream_core::packer::PackValue<#ty>>::pack(self, #ident)?;)
 }
 
 fn decode_named_field(f: &Field) -> TokenStream2 {
     let field = &f.ident;
-    quote!(let #field = self.unpack()?;)
+    let ty = &f.ty;
+    quote!(let #field = <Self as ::packstream_core::unpacker::UnpackValue<#ty>>::unpack(self)?;)
 }
 
-fn decode_unnamed_field(i: usize) -> TokenStream2 {
+fn decode_unnamed_field(i: usize, f: &Field) -> TokenStream2 {
     let ident = gen_ident(i);
-    quote!(let #ident = self.unpack()?;)
+    let ty = &f.ty;
+    quote!(let #ident = <Self as ::packstream_core::unpacker::UnpackValue<#ty>>::unpack(self)?;)
 }
 
 fn unpack_named_field(f: &Field) -> TokenStream2 {
@@ -238,8 +466,23 @@ fn unpack_unnamed_field(i: usize) -> TokenStream2 {
 }
 
 fn gen_ident(i: usize) -> Ident {
-    match ALPHA.get(i..i + 1) {
-        Some(ref c) => Ident::new(c, Span::call_site()),
-        _ => panic!("Unnamed variant field overflow")
-    }
+    format_ident!("f{}", i)
+}
+
+/// Checks that a signature literal fits in a byte.
+fn signature(lit: LitInt) -> Result<LitInt, syn::Error> {
+    lit.base10_parse::<u8>()
+        .map(|_| lit.clone())
+        .map_err(|_| syn::Error::new_spanned(
+            &lit, "struct signature must be in range 0x00..=0xFF"
+        ))
+}
+
+/// Clones `generics`, adding a where predicate for each type parameter.
+fn bounded<F>(generics: &Generics, predicate: F) -> Generics
+    where F: Fn(&Ident) -> WherePredicate {
+    let mut bounded = generics.clone();
+    let predicates = generics.type_params().map(|param| predicate(&param.ident));
+    bounded.make_where_clause().predicates.extend(predicates);
+    bounded
 }
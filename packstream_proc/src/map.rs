@@ -0,0 +1,237 @@
+//! Code generation of the `FromMap`, `IntoMap` and `FromRow` derives,
+//! converting a struct to and from a PackStream map or a row keyed by field
+//! name.
+use std::collections::HashSet;
+use proc_macro2::{TokenStream as TokenStream2, Span};
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Generics, Ident, Lit, Meta, NestedMeta, Path, Type};
+use syn::parse_quote;
+use crate::bounded;
+
+/// Field of a struct, along with its `#[map(...)]` or `#[row(...)]`
+/// attributes.
+struct MapField<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    /// Key of the field in the map, its name unless renamed.
+    key: String,
+    /// Set to `Default::default()` when the key is missing or null.
+    default: bool,
+    /// Neither read nor written, set to `Default::default()`.
+    skip: bool,
+}
+
+pub fn expand_from_map(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let v1 = crate_path(input, "map")?;
+    let fields = map_fields(input, "FromMap", "map")?;
+    let name = &input.ident;
+    let map_var = Ident::new("map", Span::mixed_site());
+    let values = take_values(&fields, &map_var, &v1);
+
+    let generics = with_defaults(
+        bounded(&input.generics, |param| parse_quote!(#param: #v1::convert::FromValue)),
+        &fields
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+    impl #impl_generics #v1::convert::FromMap for #name #ty_generics #where_clause {
+        fn from_map(
+            mut #map_var: ::std::collections::HashMap<::std::string::String, #v1::value::Value>
+        ) -> ::std::result::Result<Self, #v1::convert::ConversionError> {
+            ::std::result::Result::Ok(#name {
+                #(#values),*
+            })
+        }
+    }
+
+    impl #impl_generics #v1::convert::FromValue for #name #ty_generics #where_clause {
+        fn from_value(value: #v1::value::Value)
+                      -> ::std::result::Result<Self, #v1::convert::ConversionError> {
+            #v1::convert::into_properties(value)
+                .and_then(<Self as #v1::convert::FromMap>::from_map)
+        }
+    }
+    })
+}
+
+pub fn expand_into_map(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let v1 = crate_path(input, "map")?;
+    let fields = map_fields(input, "IntoMap", "map")?;
+    let name = &input.ident;
+    let map_var = Ident::new("map", Span::mixed_site());
+
+    let written = fields.iter().filter(|f| !f.skip).collect::<Vec<_>>();
+    let size = written.len();
+    let inserts = written.iter().map(|f| {
+        let ident = f.ident;
+        let key = &f.key;
+        quote!(#map_var.insert(
+            ::std::string::String::from(#key),
+            #v1::convert::IntoValue::into_value(self.#ident)
+        );)
+    });
+
+    let generics = bounded(&input.generics, |param| parse_quote!(
+        #param: #v1::convert::IntoValue
+    ));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+    impl #impl_generics #v1::convert::IntoMap for #name #ty_generics #where_clause {
+        fn into_map(self)
+                    -> ::std::collections::HashMap<::std::string::String, #v1::value::Value> {
+            let mut #map_var = ::std::collections::HashMap::with_capacity(#size);
+            #(#inserts)*
+            #map_var
+        }
+    }
+
+    impl #impl_generics #v1::convert::IntoValue for #name #ty_generics #where_clause {
+        fn into_value(self) -> #v1::value::Value {
+            #v1::value::Value::Map(<Self as #v1::convert::IntoMap>::into_map(self))
+        }
+    }
+    })
+}
+
+pub fn expand_from_row(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let v1 = crate_path(input, "row")?;
+    let fields = map_fields(input, "FromRow", "row")?;
+    let name = &input.ident;
+    let map_var = Ident::new("map", Span::mixed_site());
+    let values = take_values(&fields, &map_var, &v1);
+
+    let generics = with_defaults(
+        bounded(&input.generics, |param| parse_quote!(#param: #v1::convert::FromValue)),
+        &fields
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+    impl #impl_generics #v1::row::FromRow for #name #ty_generics #where_clause {
+        fn from_row(row: #v1::row::Row)
+                    -> ::std::result::Result<Self, #v1::convert::ConversionError> {
+            let mut #map_var = row.into_map();
+            ::std::result::Result::Ok(#name {
+                #(#values),*
+            })
+        }
+    }
+    })
+}
+
+/// Field initializers taking the value of each key out of `map`.
+fn take_values(fields: &[MapField], map: &Ident, v1: &Path) -> Vec<TokenStream2> {
+    fields.iter().map(|f| {
+        let ident = f.ident;
+        let key = &f.key;
+        let value = if f.skip {
+            quote!(::std::default::Default::default())
+        }
+        else if f.default {
+            quote!(#v1::convert::take_or_default(&mut #map, #key)?)
+        }
+        else {
+            quote!(#v1::convert::take(&mut #map, #key)?)
+        };
+        quote!(#ident: #value)
+    }).collect()
+}
+
+/// Adds a `Default` bound to the types of defaulted fields of a generic
+/// struct.
+fn with_defaults(mut generics: Generics, fields: &[MapField]) -> Generics {
+    if generics.params.is_empty() {
+        return generics;
+    }
+    let predicates = &mut generics.make_where_clause().predicates;
+    for f in fields.iter().filter(|f| f.skip || f.default) {
+        let ty = f.ty;
+        predicates.push(parse_quote!(#ty: ::std::default::Default));
+    }
+    generics
+}
+
+/// Path of the `packstream_v1` crate, `::packstream_v1` unless given by a
+/// `#[map(crate = "...")]` or `#[row(crate = "...")]` attribute on the struct.
+fn crate_path(input: &DeriveInput, attr_name: &str) -> Result<Path, Error> {
+    let mut path = parse_quote!(::packstream_v1);
+    for attr in input.attrs.iter().filter(|a| a.path.is_ident(attr_name)) {
+        let metas = match attr.parse_meta()? {
+            Meta::List(list) => list.nested,
+            meta => return Err(Error::new_spanned(
+                meta, format!("expected #[{}(...)]", attr_name)
+            ))
+        };
+        for meta in metas {
+            match meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") =>
+                    match nv.lit {
+                        Lit::Str(lit) => path = lit.parse()?,
+                        lit => return Err(Error::new_spanned(
+                            lit, "crate must be a path string"
+                        ))
+                    },
+                meta => return Err(Error::new_spanned(
+                    meta, format!("unknown {} struct attribute", attr_name)
+                ))
+            }
+        }
+    }
+    Ok(path)
+}
+
+fn map_fields<'a>(input: &'a DeriveInput, derive: &str, attr_name: &str)
+                  -> Result<Vec<MapField<'a>>, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(
+                &input.ident, format!("{} can only be derived for structs with named fields", derive)
+            ))
+        },
+        _ => return Err(Error::new_spanned(
+            &input.ident, format!("{} can only be derived for structs", derive)
+        ))
+    };
+
+    let mut keys = HashSet::new();
+    fields.iter().map(|f| {
+        let ident = f.ident.as_ref().expect("named field");
+        let mut field = MapField {
+            ident,
+            ty: &f.ty,
+            key: ident.to_string().trim_start_matches("r#").to_owned(),
+            default: false,
+            skip: false,
+        };
+        for attr in f.attrs.iter().filter(|a| a.path.is_ident(attr_name)) {
+            let metas = match attr.parse_meta()? {
+                Meta::List(list) => list.nested,
+                meta => return Err(Error::new_spanned(
+                    meta, format!("expected #[{}(...)]", attr_name)
+                ))
+            };
+            for meta in metas {
+                match meta {
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("default") => field.default = true,
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("skip") => field.skip = true,
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") =>
+                        match nv.lit {
+                            Lit::Str(lit) => field.key = lit.value(),
+                            lit => return Err(Error::new_spanned(
+                                lit, "rename must be a string literal"
+                            ))
+                        },
+                    meta => return Err(Error::new_spanned(
+                        meta, format!("unknown {} field attribute", attr_name)
+                    ))
+                }
+            }
+        }
+        if !field.skip && !keys.insert(field.key.clone()) {
+            return Err(Error::new_spanned(
+                ident, format!("duplicate {} key {:?}", attr_name, field.key)
+            ));
+        }
+        Ok(field)
+    }).collect()
+}
@@ -0,0 +1,443 @@
+//! Code generation of the `PackValue` and `UnpackValue` impls of a struct,
+//! shared by the `bolt_packstream` attribute and the `BoltStruct` derive.
+use proc_macro2::{TokenStream as TokenStream2, Span};
+use quote::{quote, format_ident};
+use syn::{Attribute, Error, Fields, Generics, Ident, Index, Lit, LitInt, Member};
+use syn::{parse_quote, Meta, NestedMeta, Path, Type};
+use crate::{bounded, signature, MAX_STRUCT_SIZE};
+
+/// Options of a field, set with `#[bolt(...)]` attributes.
+#[derive(Default)]
+struct FieldOptions {
+    /// Not packed, unpacked as `Default::default()`.
+    skip: bool,
+    /// Unpacked as `Default::default()` when the packed value is null.
+    default: bool,
+    /// Trailing field which may be absent from the packed struct, unpacked
+    /// as `Default::default()` in that case.
+    optional: bool,
+    /// Module providing `pack` and `unpack` functions for the field.
+    with: Option<Path>,
+    /// First protocol version the field is packed in.
+    since: Option<(u8, u8)>,
+    /// Last protocol version the field is packed in.
+    until: Option<(u8, u8)>,
+}
+
+impl FieldOptions {
+    fn is_versioned(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
+    }
+}
+
+/// Options of a struct, set with its `#[bolt(...)]` attribute or the
+/// arguments of `bolt_packstream`.
+pub struct StructOptions {
+    pub sign: LitInt,
+    /// Skips packed fields past the last declared one instead of failing.
+    pub skip_extra: bool,
+}
+
+impl StructOptions {
+    /// Reads a signature literal followed by optional flags.
+    pub fn from_metas<I>(metas: I, name: &Ident) -> Result<Self, Error>
+        where I: IntoIterator<Item = NestedMeta> {
+        let mut sign = None;
+        let mut skip_extra = false;
+        for meta in metas {
+            match meta {
+                NestedMeta::Lit(Lit::Int(lit)) => sign = Some(signature(lit)?),
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("signature") =>
+                    match nv.lit {
+                        Lit::Int(lit) => sign = Some(signature(lit)?),
+                        lit => return Err(Error::new_spanned(
+                            lit, "signature must be an integer literal"
+                        ))
+                    },
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("skip_extra") => skip_extra = true,
+                meta => return Err(Error::new_spanned(
+                    meta, "unknown bolt struct attribute"
+                ))
+            }
+        }
+        let sign = sign.ok_or_else(|| Error::new_spanned(
+            name, "missing #[bolt(signature = ..)] attribute"
+        ))?;
+        Ok(StructOptions { sign, skip_extra })
+    }
+
+    /// Reads the `#[bolt(...)]` attributes of a derived struct.
+    pub fn from_attrs(attrs: &[Attribute], name: &Ident) -> Result<Self, Error> {
+        StructOptions::from_metas(bolt_metas(attrs)?, name)
+    }
+}
+
+struct Field<'a> {
+    member: Member,
+    ty: &'a Type,
+    options: FieldOptions,
+}
+
+pub struct Structure<'a> {
+    name: &'a Ident,
+    generics: &'a Generics,
+    options: StructOptions,
+    fields: &'a Fields,
+    defs: Vec<Field<'a>>,
+}
+
+impl<'a> Structure<'a> {
+    /// Reads the fields of a struct and their `#[bolt(...)]` attributes.
+    pub fn new(name: &'a Ident,
+               generics: &'a Generics,
+               options: StructOptions,
+               fields: &'a Fields) -> Result<Self, Error> {
+        let defs = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| Ok(Field {
+                member: match &f.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(Index::from(i))
+                },
+                ty: &f.ty,
+                options: field_options(&f.attrs)?
+            }))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let packed = defs.iter().filter(|f| !f.options.skip);
+        if packed.clone().count() > MAX_STRUCT_SIZE {
+            return Err(Error::new_spanned(
+                name,
+                format!("a struct cannot pack more than {} fields", MAX_STRUCT_SIZE)
+            ));
+        }
+        let mut optional = None;
+        for f in packed {
+            match (&optional, f.options.optional) {
+                (None, true) => optional = Some(&f.member),
+                (Some(_), false) => return Err(Error::new_spanned(
+                    &f.member,
+                    "optional fields must come after all other packed fields"
+                )),
+                _ => ()
+            }
+        }
+
+        Ok(Structure { name, generics, options, fields, defs })
+    }
+
+    pub fn expand(&self) -> TokenStream2 {
+        let core = quote!(::packstream_core);
+        let name = self.name;
+        let sign = &self.options.sign;
+        let packed = self.defs.iter().filter(|f| !f.options.skip).collect::<Vec<_>>();
+        let size = packed.len();
+        let min_size = packed.iter().filter(|f| !f.options.optional).count();
+
+        let size_var = Ident::new("size", Span::mixed_site());
+        let sign_var = Ident::new("sign", Span::mixed_site());
+        let version_var = Ident::new("version", Span::mixed_site());
+        let versioned = packed.iter().any(|f| f.options.is_versioned());
+        let get_version = if versioned {
+            quote!(let #version_var = self.version();)
+        }
+        else {
+            quote!()
+        };
+
+        let pack_calls = packed.iter().map(|f| {
+            let member = &f.member;
+            let ty = f.ty;
+            let call = match &f.options.with {
+                Some(with) => quote!(#with::pack(self, s.#member)?;),
+                None => quote!(
+                    <Self as #core::packer::PackValue<#ty>>::pack(self, s.#member)?;
+                )
+            };
+            match in_version(f, &version_var) {
+                Some(cond) => quote!(if #cond { #call }),
+                None => call
+            }
+        });
+        let pack_size = count(&packed, &version_var);
+
+        let bindings = (0..self.defs.len())
+            .map(|i| format_ident!("f{}", i, span = Span::mixed_site()))
+            .collect::<Vec<_>>();
+        let mut index: usize = 0;
+        let field_values = self.defs.iter().zip(bindings.iter()).map(|(f, binding)| {
+            let value = if f.options.skip {
+                quote!(::std::default::Default::default())
+            }
+            else {
+                let value = self.unpack_field(f);
+                let position = count(&packed[..index], &version_var);
+                index += 1;
+                let cond = match (in_version(f, &version_var), f.options.optional) {
+                    (None, false) => None,
+                    (None, true) => Some(quote!(#position < #size_var)),
+                    (Some(cond), false) => Some(cond),
+                    (Some(cond), true) => Some(quote!(#cond && #position < #size_var))
+                };
+                match cond {
+                    Some(cond) => quote!(if #cond { #value }
+                                         else { ::std::default::Default::default() }),
+                    None => value
+                }
+            };
+            quote!(let #binding = #value;)
+        }).collect::<Vec<_>>();
+
+        let required = packed.iter().copied().filter(|f| !f.options.optional).collect::<Vec<_>>();
+        let (min, max, size_bounds) = if versioned {
+            let min_var = Ident::new("min", Span::mixed_site());
+            let max_var = Ident::new("max", Span::mixed_site());
+            let min_count = count(&required, &version_var);
+            let max_count = count(&packed, &version_var);
+            (quote!(#min_var), quote!(#max_var), quote! {
+                let #min_var: usize = #min_count;
+                let #max_var: usize = #max_count;
+            })
+        }
+        else {
+            (quote!(#min_size), quote!(#size), quote!())
+        };
+        let mismatch = match (self.options.skip_extra, min_size) {
+            (true, 0) => None,
+            (true, _) => Some(quote!(#size_var < #min)),
+            (false, _) if !versioned && min_size == size => Some(quote!(#size_var != #size)),
+            (false, _) => Some(quote!(!(#min..=#max).contains(&#size_var)))
+        };
+        let check_size = mismatch.map(|mismatch| quote! {
+            #size_bounds
+            if #mismatch {
+                return ::std::result::Result::Err(
+                    #core::error::BoltError::UnpackStructFieldCountMismatch {
+                        min: #min,
+                        max: #max,
+                        found: #size_var
+                    }
+                );
+            }
+        });
+        let skip_extra = if self.options.skip_extra {
+            let max_count = count(&packed, &version_var);
+            quote!(for _ in #max_count..#size_var { self.skip_value()?; })
+        }
+        else {
+            quote!()
+        };
+
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let pack_generics = bounded(self.generics, |param| parse_quote!(
+            #core::packer::Packer: #core::packer::PackValue<#param>
+        ));
+        let (pack_impl_generics, _, pack_where_clause) = pack_generics.split_for_impl();
+        let mut unpack_generics = bounded(self.generics, |param| parse_quote!(
+            #core::unpacker::Unpacker: #core::unpacker::UnpackValue<#param>
+        ));
+        if !self.generics.params.is_empty() {
+            let defaulted = self.defs
+                .iter()
+                .filter(|f| f.options.skip || f.options.default || f.options.optional
+                    || f.options.is_versioned())
+                .map(|f| f.ty);
+            let predicates = &mut unpack_generics.make_where_clause().predicates;
+            for ty in defaulted {
+                predicates.push(parse_quote!(#ty: ::std::default::Default));
+            }
+        }
+        let (unpack_impl_generics, _, unpack_where_clause) = unpack_generics.split_for_impl();
+
+        let names = packed.iter().map(|f| match &f.member {
+            Member::Named(ident) => ident.to_string().trim_start_matches("r#").to_owned(),
+            Member::Unnamed(index) => index.index.to_string()
+        });
+        let skip_extra_flag = self.options.skip_extra;
+        let ranges = packed.iter().map(|f| version_range(&f.options));
+
+        let members = self.defs.iter().map(|f| &f.member);
+        let construct = match self.fields {
+            Fields::Named(_) => quote!(#name { #(#members: #bindings),* }),
+            Fields::Unnamed(_) => quote!(#name ( #(#bindings),* )),
+            Fields::Unit => quote!(#name)
+        };
+
+        quote! {
+        impl #impl_generics #core::structure::BoltStructure
+        for #name #ty_generics #where_clause {
+            const SIGNATURE: u8 = #sign;
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+            const VERSIONS: &'static [#core::version::VersionRange] = &[#(#ranges),*];
+            const MIN_SIZE: usize = #min_size;
+            const SKIP_EXTRA: bool = #skip_extra_flag;
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Number of fields packed by the default protocol version.
+            pub fn struct_size() -> usize {
+                <Self as #core::structure::BoltStructure>::size_at(
+                    ::std::default::Default::default()
+                )
+            }
+            pub fn struct_sign() -> u8 {
+                <Self as #core::structure::BoltStructure>::SIGNATURE
+            }
+        }
+
+        impl #pack_impl_generics #core::packer::PackValue<#name #ty_generics>
+        for #core::packer::Packer #pack_where_clause {
+            fn pack(&mut self, s: #name #ty_generics)
+                    -> ::std::result::Result<(), #core::error::BoltError> {
+                #get_version
+                self.pack_struct_header(#pack_size, #sign)?;
+                #(#pack_calls)*
+                ::std::result::Result::Ok(())
+            }
+        }
+
+        impl #unpack_impl_generics #core::unpacker::UnpackValue<#name #ty_generics>
+        for #core::unpacker::Unpacker #unpack_where_clause {
+            fn unpack(&mut self)
+                      -> ::std::result::Result<#name #ty_generics, #core::error::BoltError> {
+                let #size_var = self.unpack_struct_header()? as usize;
+                let #sign_var = self.unpack_struct_signature()?;
+                if #sign_var != #sign {
+                    return ::std::result::Result::Err(
+                        #core::error::BoltError::UnpackUnexpectedStructSignature {
+                            expected: #sign,
+                            found: #sign_var
+                        }
+                    );
+                }
+                #get_version
+                #check_size
+                #(#field_values)*
+                #skip_extra
+                ::std::result::Result::Ok(#construct)
+            }
+        }
+        }
+    }
+
+    fn unpack_field(&self, f: &Field) -> TokenStream2 {
+        let core = quote!(::packstream_core);
+        let ty = f.ty;
+        let value = match &f.options.with {
+            Some(with) => quote!(#with::unpack(self)?),
+            None => quote!(<Self as #core::unpacker::UnpackValue<#ty>>::unpack(self)?)
+        };
+        if f.options.default {
+            quote!(if self.input.peek()? == #core::consts::NULL {
+                self.input.read_u8()?;
+                ::std::default::Default::default()
+            } else { #value })
+        }
+        else {
+            value
+        }
+    }
+}
+
+fn field_options(attrs: &[Attribute]) -> Result<FieldOptions, Error> {
+    let mut options = FieldOptions::default();
+    let mut until_meta = None;
+    for meta in bolt_metas(attrs)? {
+        match meta {
+            NestedMeta::Meta(Meta::Path(p)) if p.is_ident("skip") => options.skip = true,
+            NestedMeta::Meta(Meta::Path(p)) if p.is_ident("default") => options.default = true,
+            NestedMeta::Meta(Meta::Path(p)) if p.is_ident("optional") => options.optional = true,
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("with") =>
+                match nv.lit {
+                    Lit::Str(lit) => options.with = Some(lit.parse()?),
+                    lit => return Err(Error::new_spanned(
+                        lit, "with must be a module path string"
+                    ))
+                },
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("since") =>
+                options.since = Some(version(&nv.lit)?),
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("until") => {
+                options.until = Some(version(&nv.lit)?);
+                until_meta = Some(nv);
+            },
+            meta => return Err(Error::new_spanned(
+                meta, "unknown bolt field attribute"
+            ))
+        }
+    }
+    // Checked once every attribute is read, whatever their order.
+    if let (Some(since), Some(until), Some(nv)) = (options.since, options.until, until_meta) {
+        if since > until {
+            return Err(Error::new_spanned(
+                nv, "field cannot be packed until a version before its since version"
+            ));
+        }
+    }
+    Ok(options)
+}
+
+/// Reads a `"major.minor"` protocol version.
+fn version(lit: &Lit) -> Result<(u8, u8), Error> {
+    let error = || Error::new_spanned(lit, "expected a protocol version like \"5.0\"");
+    let s = match lit {
+        Lit::Str(s) => s.value(),
+        _ => return Err(error())
+    };
+    let mut parts = s.splitn(2, '.').map(str::parse::<u8>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), None) => Ok((major, 0)),
+        (Some(Ok(major)), Some(Ok(minor))) => Ok((major, minor)),
+        _ => Err(error())
+    }
+}
+
+fn version_range(options: &FieldOptions) -> TokenStream2 {
+    let bound = |v: Option<(u8, u8)>| match v {
+        Some((major, minor)) => quote!(
+            ::std::option::Option::Some(::packstream_core::version::Version::new(#major, #minor))
+        ),
+        None => quote!(::std::option::Option::None)
+    };
+    let since = bound(options.since);
+    let until = bound(options.until);
+    quote!(::packstream_core::version::VersionRange::new(#since, #until))
+}
+
+/// Checks whether a versioned field is packed in `version`.
+fn in_version(f: &Field, version: &Ident) -> Option<TokenStream2> {
+    if f.options.is_versioned() {
+        let range = version_range(&f.options);
+        Some(quote!(#range.contains(#version)))
+    }
+    else {
+        None
+    }
+}
+
+/// Counts the fields packed in `version`, as a literal when none of them is
+/// versioned.
+fn count(fields: &[&Field], version: &Ident) -> TokenStream2 {
+    let fixed = fields.iter().filter(|f| !f.options.is_versioned()).count();
+    let conds = fields
+        .iter()
+        .filter_map(|f| in_version(f, version))
+        .map(|cond| quote!(usize::from(#cond)))
+        .collect::<Vec<_>>();
+    match (fixed, conds.is_empty()) {
+        (_, true) => quote!(#fixed),
+        (0, false) => quote!(#(#conds)+*),
+        (_, false) => quote!(#fixed #(+ #conds)*)
+    }
+}
+
+fn bolt_metas(attrs: &[Attribute]) -> Result<Vec<NestedMeta>, Error> {
+    let mut metas = vec![];
+    for attr in attrs.iter().filter(|a| a.path.is_ident("bolt")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => metas.extend(list.nested),
+            meta => return Err(Error::new_spanned(meta, "expected #[bolt(...)]"))
+        }
+    }
+    Ok(metas)
+}
@@ -0,0 +1,253 @@
+//! Access to values nested in lists, maps and graph entities.
+//!
+//! Rather than matching each level of a value, a nested value is reached by
+//! its path, either as a [JSON pointer](https://tools.ietf.org/html/rfc6901)
+//! or as a list of [Key]:
+//!
+//! ```ignore
+//! let city = record.fields[2].pointer("/address/0/city");
+//! let city = record.fields[2].get_path(&["address".into(), 0.into(), "city".into()]);
+//! let city = record.fields[2].get_path(&keys!["address", 0, "city"]);
+//! ```
+//!
+//! Names are looked up in maps and in the properties of nodes and
+//! relationships, indexes in lists. Lookups return `None` as soon as a level
+//! is missing or holds another type.
+use std::convert::TryFrom;
+use std::collections::HashMap;
+use crate::structs::Struct;
+use crate::value::Value;
+
+/// Step of a path into a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key<'a> {
+  /// Key of a map or property of a node or relationship.
+  Name(&'a str),
+  /// Index in a list, negative ones counting from the end as in Cypher.
+  Index(i64)
+}
+
+impl<'a> From<&'a str> for Key<'a> {
+  fn from(name: &'a str) -> Self {
+    Key::Name(name)
+  }
+}
+
+impl<'a> From<&'a String> for Key<'a> {
+  fn from(name: &'a String) -> Self {
+    Key::Name(name)
+  }
+}
+
+impl From<i32> for Key<'_> {
+  fn from(index: i32) -> Self {
+    Key::Index(index.into())
+  }
+}
+
+impl From<i64> for Key<'_> {
+  fn from(index: i64) -> Self {
+    Key::Index(index)
+  }
+}
+
+impl From<usize> for Key<'_> {
+  fn from(index: usize) -> Self {
+    Key::Index(i64::try_from(index).unwrap_or(i64::MAX))
+  }
+}
+
+impl Value {
+  /// Gets the value at `key`, one level down.
+  pub fn get_key(&self, key: Key) -> Option<&Value> {
+    match (self, key) {
+      (Value::List(xs), Key::Index(i)) => list_index(xs.len(), i).map(|i| &xs[i]),
+      (value, Key::Name(name)) => properties(value)?.get(name),
+      _ => None
+    }
+  }
+
+  pub fn get_key_mut(&mut self, key: Key) -> Option<&mut Value> {
+    match (self, key) {
+      (Value::List(xs), Key::Index(i)) => list_index(xs.len(), i).map(move |i| &mut xs[i]),
+      (value, Key::Name(name)) => properties_mut(value)?.get_mut(name),
+      _ => None
+    }
+  }
+
+  /// Gets the value at the end of `path`, the value itself when it is empty.
+  pub fn get_path(&self, path: &[Key]) -> Option<&Value> {
+    path.iter().try_fold(self, |value, key| value.get_key(*key))
+  }
+
+  pub fn get_path_mut(&mut self, path: &[Key]) -> Option<&mut Value> {
+    path.iter().try_fold(self, |value, key| value.get_key_mut(*key))
+  }
+
+  /**
+   * Gets the value at the JSON pointer `pointer`, such as `/address/0/city`.
+   * Segments are read as names, or as indexes in lists, `~1` and `~0`
+   * standing for `/` and `~`. The empty pointer is the value itself.
+   */
+  pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+    if pointer.is_empty() {
+      return Some(self);
+    }
+    pointer.strip_prefix('/')?.split('/').try_fold(self, |value, segment| {
+      let segment = unescape(segment);
+      value.get_key(segment_key(value, &segment)?)
+    })
+  }
+
+  pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+    if pointer.is_empty() {
+      return Some(self);
+    }
+    pointer.strip_prefix('/')?.split('/').try_fold(self, |value, segment| {
+      let segment = unescape(segment);
+      let key = segment_key(value, &segment)?;
+      value.get_key_mut(key)
+    })
+  }
+}
+
+/// Position of `index` in a list of `len` values.
+fn list_index(len: usize, index: i64) -> Option<usize> {
+  let index = if index < 0 {
+    i64::try_from(len).ok()?.checked_add(index)?
+  }
+  else {
+    index
+  };
+  usize::try_from(index).ok().filter(|i| *i < len)
+}
+
+fn properties(value: &Value) -> Option<&HashMap<String, Value>> {
+  match value {
+    Value::Map(ps) => Some(ps),
+    Value::Struct(Struct::Node(x)) => Some(&x.properties),
+    Value::Struct(Struct::Relationship(x)) => Some(&x.properties),
+    Value::Struct(Struct::UnboundRelationship(x)) => Some(&x.properties),
+    _ => None
+  }
+}
+
+fn properties_mut(value: &mut Value) -> Option<&mut HashMap<String, Value>> {
+  match value {
+    Value::Map(ps) => Some(ps),
+    Value::Struct(Struct::Node(x)) => Some(&mut x.properties),
+    Value::Struct(Struct::Relationship(x)) => Some(&mut x.properties),
+    Value::Struct(Struct::UnboundRelationship(x)) => Some(&mut x.properties),
+    _ => None
+  }
+}
+
+fn unescape(segment: &str) -> String {
+  segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// Reads a segment as an index in lists, as a name elsewhere. Indexes are
+/// plain decimal numbers, as per the JSON pointer specification.
+fn segment_key<'a>(value: &Value, segment: &'a str) -> Option<Key<'a>> {
+  match value {
+    Value::List(_) => {
+      let is_index = !segment.is_empty()
+        && segment.bytes().all(|b| b.is_ascii_digit())
+        && (segment == "0" || !segment.starts_with('0'));
+      if is_index { segment.parse().ok().map(Key::Index) } else { None }
+    },
+    _ => Some(Key::Name(segment))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::structs::{Node, Relationship};
+  use super::*;
+
+  fn person() -> Value {
+    value!({
+      "name": "Alice",
+      "address": [{ "city": "Paris" }, { "city": "Lyon" }],
+      "a/b": 1,
+      "m~n": 2,
+      "": 3
+    })
+  }
+
+  #[test]
+  fn reads_pointers() {
+    let person = person();
+    assert_eq!(person.pointer(""), Some(&person));
+    assert_eq!(person.pointer("/name"), Some(&Value::from("Alice")));
+    assert_eq!(person.pointer("/address/1/city"), Some(&Value::from("Lyon")));
+    assert_eq!(person.pointer("/a~1b"), Some(&Value::Int(1)));
+    assert_eq!(person.pointer("/m~0n"), Some(&Value::Int(2)));
+    assert_eq!(person.pointer("/"), Some(&Value::Int(3)));
+    assert_eq!(person.pointer("name"), None);
+    assert_eq!(person.pointer("/age"), None);
+    assert_eq!(person.pointer("/name/0"), None);
+  }
+
+  #[test]
+  fn reads_pointer_indexes_as_decimals() {
+    let person = person();
+    assert_eq!(person.pointer("/address/0/city"), Some(&Value::from("Paris")));
+    assert_eq!(person.pointer("/address/01/city"), None);
+    assert_eq!(person.pointer("/address/-1/city"), None);
+    assert_eq!(person.pointer("/address/+1/city"), None);
+    assert_eq!(person.pointer("/address/2"), None);
+    assert_eq!(person.pointer("/address/99999999999999999999"), None);
+  }
+
+  #[test]
+  fn reads_paths() {
+    let person = person();
+    assert_eq!(person.get_path(&[]), Some(&person));
+    assert_eq!(person.get_path(&keys!["address", 0, "city"]), Some(&Value::from("Paris")));
+    assert_eq!(person.get_path(&keys!["address", -1, "city"]), Some(&Value::from("Lyon")));
+    assert_eq!(person.get_path(&keys!["address", -3]), None);
+    assert_eq!(person.get_path(&keys!["address", 2]), None);
+    assert_eq!(person.get_path(&keys!["address", "0"]), None);
+    assert_eq!(person.get_path(&keys!["a/b"]), Some(&Value::Int(1)));
+  }
+
+  #[test]
+  fn reads_entity_properties() {
+    let node = Value::Struct(Struct::Node(Node {
+      id: 1,
+      labels: vec![],
+      properties: hashmap! { "tags".to_owned() => value!(["a", "b"]) },
+      element_id: None
+    }));
+    let relationship = Value::Struct(Struct::Relationship(Relationship {
+      id: 2,
+      start_node_id: 1,
+      end_node_id: 1,
+      type_name: "KNOWS".to_owned(),
+      properties: hashmap! { "since".to_owned() => Value::Int(2000) },
+      element_id: None,
+      start_node_element_id: None,
+      end_node_element_id: None
+    }));
+    let entities = value!([node, relationship]);
+    assert_eq!(entities.pointer("/0/tags/1"), Some(&Value::from("b")));
+    assert_eq!(entities.get_path(&keys![1, "since"]), Some(&Value::Int(2000)));
+    assert_eq!(entities.pointer("/1/type_name"), None);
+  }
+
+  #[test]
+  fn mutates_nested_values() {
+    let mut person = person();
+    *person.pointer_mut("/address/0/city").unwrap() = Value::from("Nice");
+    *person.get_path_mut(&keys!["address", -1, "city"]).unwrap() = Value::from("Lille");
+    if let Some(Value::Map(ps)) = person.pointer_mut("") {
+      ps.remove("");
+    }
+    assert_eq!(person.pointer("/address/0/city"), Some(&Value::from("Nice")));
+    assert_eq!(person.pointer("/address/1/city"), Some(&Value::from("Lille")));
+    assert_eq!(person.pointer("/"), None);
+    assert_eq!(person.pointer_mut("/address/01"), None);
+    assert_eq!(person.get_path_mut(&keys!["address", 2]), None);
+  }
+}
@@ -0,0 +1,651 @@
+//! Conversions between [Value] and Rust types.
+//!
+//! [FromValue] and [IntoValue] are implemented for the primitive types, the
+//! collections and the graph, temporal and spatial structures, along with
+//! the matching `From` and `TryFrom` implementations of [Value]:
+//!
+//! ```ignore
+//! let params: HashMap<String, Value> = hashmap! {
+//!   "name".into() => "Alice".into(),
+//!   "ids".into() => vec![1, 2, 3].into()
+//! };
+//! let id = i64::try_from(value)?;
+//! ```
+//!
+//! Structs are mapped to and from
+//! PackStream maps with `#[derive(FromMap, IntoMap)]`, keys being matched
+//! with field names:
+//!
+//! ```ignore
+//! #[derive(FromMap, IntoMap)]
+//! pub struct Person {
+//!   pub name: String,
+//!   #[map(rename = "yearOfBirth")]
+//!   pub born: i64,
+//!   /// `None` when the key is missing or null.
+//!   pub email: Option<String>,
+//!   /// `Default::default()` when the key is missing or null.
+//!   #[map(default)]
+//!   pub tags: Vec<String>,
+//!   /// Nested maps are converted with their own derive.
+//!   pub address: Address
+//! }
+//!
+//! let person = Person::from_map(node.properties)?;
+//! ```
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use crate::value::Value;
+use crate::structs::{Struct, Node, Relationship, Path, UnboundRelationship};
+use crate::temporal::*;
+use crate::spatial::{Point2D, Point3D};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+  /// A required key is missing from the map.
+  MissingKey(String),
+  TypeMismatch { expected: &'static str, found: &'static str },
+  /// An integer does not fit in the requested type.
+  OutOfRange { expected: &'static str, value: i64 },
+  /// A finite float does not fit in the requested type.
+  FloatOutOfRange { expected: &'static str, value: f64 },
+  /// A sequence does not have the requested number of values.
+  LengthMismatch { expected: usize, found: usize },
+  /// Conversion of the value of a key failed.
+  InKey(String, Box<ConversionError>),
+  /// Conversion of an item of a list failed.
+  InIndex(usize, Box<ConversionError>)
+}
+
+impl ConversionError {
+  pub fn in_key(self, key: &str) -> Self {
+    ConversionError::InKey(key.to_owned(), Box::new(self))
+  }
+
+  pub fn in_index(self, index: usize) -> Self {
+    ConversionError::InIndex(index, Box::new(self))
+  }
+
+  pub(crate) fn mismatch(expected: &'static str, found: &Value) -> Self {
+    ConversionError::TypeMismatch { expected, found: found.kind() }
+  }
+}
+
+impl fmt::Display for ConversionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ConversionError::MissingKey(key) => write!(f, "missing key {:?}", key),
+      ConversionError::TypeMismatch { expected, found } =>
+        write!(f, "expected {}, found {}", expected, found),
+      ConversionError::OutOfRange { expected, value } =>
+        write!(f, "{} is out of range of {}", value, expected),
+      ConversionError::FloatOutOfRange { expected, value } =>
+        write!(f, "{} is out of range of {}", value, expected),
+      ConversionError::LengthMismatch { expected, found } =>
+        write!(f, "expected {} values, found {}", expected, found),
+      ConversionError::InKey(key, e) => write!(f, "in key {:?}: {}", key, e),
+      ConversionError::InIndex(index, e) => write!(f, "at index {}: {}", index, e)
+    }
+  }
+}
+
+impl Error for ConversionError {}
+
+impl Value {
+  /// Name of the variant of the value, as reported by conversion errors.
+  pub fn kind(&self) -> &'static str {
+    match self {
+      Value::Null       => "Null",
+      Value::Boolean(_) => "Boolean",
+      Value::Int(_)     => "Int",
+      Value::Float(_)   => "Float",
+      Value::String(_)  => "String",
+      Value::List(_)    => "List",
+      Value::Map(_)     => "Map",
+      Value::Struct(s)  => match s {
+        Struct::Node(_)                => "Node",
+        Struct::Relationship(_)        => "Relationship",
+        Struct::Path(_)                => "Path",
+        Struct::UnboundRelationship(_) => "UnboundRelationship",
+        Struct::Date(_)                => "Date",
+        Struct::Time(_)                => "Time",
+        Struct::LocalTime(_)           => "LocalTime",
+        Struct::DateTime(_)            => "DateTime",
+        Struct::DateTimeZoneId(_)      => "DateTimeZoneId",
+        Struct::LegacyDateTime(_)      => "LegacyDateTime",
+        Struct::LegacyDateTimeZoneId(_) => "LegacyDateTimeZoneId",
+        Struct::LocalDateTime(_)       => "LocalDateTime",
+        Struct::Duration(_)            => "Duration",
+        Struct::Point2D(_)             => "Point2D",
+        Struct::Point3D(_)             => "Point3D",
+        Struct::Unknown { .. }
+        | Struct::Custom { .. }        => "Struct"
+      }
+    }
+  }
+}
+
+/// Conversion of a [Value] to a Rust type.
+pub trait FromValue: Sized {
+  fn from_value(value: Value) -> Result<Self, ConversionError>;
+
+  /// Value of a field whose key is missing from a map, `None` when the key
+  /// is required.
+  fn from_missing() -> Option<Self> {
+    None
+  }
+}
+
+/// Conversion of a Rust type to a [Value].
+pub trait IntoValue {
+  fn into_value(self) -> Value;
+}
+
+/// Conversion of a PackStream map to a Rust struct, see
+/// [derive(FromMap)](packstream_proc::FromMap).
+pub trait FromMap: Sized {
+  fn from_map(map: HashMap<String, Value>) -> Result<Self, ConversionError>;
+}
+
+/// Conversion of a Rust struct to a PackStream map, see
+/// [derive(IntoMap)](packstream_proc::IntoMap).
+pub trait IntoMap {
+  fn into_map(self) -> HashMap<String, Value>;
+}
+
+/// Gets the map of a value converted with [FromMap]: either a map or the
+/// properties of a node or a relationship.
+pub fn into_properties(value: Value) -> Result<HashMap<String, Value>, ConversionError> {
+  match value {
+    Value::Map(map) => Ok(map),
+    Value::Struct(Struct::Node(x)) => Ok(x.properties),
+    Value::Struct(Struct::Relationship(x)) => Ok(x.properties),
+    Value::Struct(Struct::UnboundRelationship(x)) => Ok(x.properties),
+    value => Err(ConversionError::mismatch("Map", &value))
+  }
+}
+
+/// Removes `key` from `map` and converts its value.
+pub fn take<T: FromValue>(map: &mut HashMap<String, Value>, key: &str)
+                          -> Result<T, ConversionError> {
+  match map.remove(key) {
+    Some(value) => T::from_value(value).map_err(|e| e.in_key(key)),
+    None => T::from_missing().ok_or_else(|| ConversionError::MissingKey(key.to_owned()))
+  }
+}
+
+/// Converts the value of `key` in `map` as [take] does, leaving it in place.
+pub fn get<T: FromValue>(map: &HashMap<String, Value>, key: &str)
+                         -> Result<T, ConversionError> {
+  match map.get(key) {
+    Some(value) => T::from_value(value.clone()).map_err(|e| e.in_key(key)),
+    None => T::from_missing().ok_or_else(|| ConversionError::MissingKey(key.to_owned()))
+  }
+}
+
+/// Removes `key` from `map` and converts its value, `Default::default()`
+/// being used when the key is missing or null.
+pub fn take_or_default<T>(map: &mut HashMap<String, Value>, key: &str)
+                          -> Result<T, ConversionError>
+  where T: FromValue + Default {
+  match map.remove(key) {
+    None | Some(Value::Null) => Ok(T::default()),
+    Some(value) => T::from_value(value).map_err(|e| e.in_key(key))
+  }
+}
+
+impl FromValue for Value {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    Ok(value)
+  }
+}
+
+impl IntoValue for Value {
+  fn into_value(self) -> Value {
+    self
+  }
+}
+
+impl FromValue for bool {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Boolean(b) => Ok(b),
+      value => Err(ConversionError::mismatch("Boolean", &value))
+    }
+  }
+}
+
+impl IntoValue for bool {
+  fn into_value(self) -> Value {
+    Value::Boolean(self)
+  }
+}
+
+macro_rules! int_conversions {
+  ($($t:ty),*) => {$(
+    impl FromValue for $t {
+      fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+          Value::Int(n) => <$t>::try_from(n).map_err(|_| ConversionError::OutOfRange {
+            expected: stringify!($t),
+            value: n
+          }),
+          value => Err(ConversionError::mismatch("Int", &value))
+        }
+      }
+    }
+  )*}
+}
+
+int_conversions!(i8, i16, i32, i64, u8, u16, u32, u64, isize, usize);
+
+macro_rules! into_int {
+  ($($t:ty),*) => {$(
+    impl IntoValue for $t {
+      fn into_value(self) -> Value {
+        Value::Int(self.into())
+      }
+    }
+  )*}
+}
+
+into_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl FromValue for f64 {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Float(n) => Ok(n),
+      value => Err(ConversionError::mismatch("Float", &value))
+    }
+  }
+}
+
+impl IntoValue for f64 {
+  fn into_value(self) -> Value {
+    Value::Float(self)
+  }
+}
+
+/// Rounds to the nearest `f32`, failing when a finite float is beyond its
+/// range rather than turning it into an infinity.
+impl FromValue for f32 {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    f64::from_value(value).and_then(|n| match n as f32 {
+      x if x.is_infinite() && n.is_finite() =>
+        Err(ConversionError::FloatOutOfRange { expected: "f32", value: n }),
+      x => Ok(x)
+    })
+  }
+}
+
+impl IntoValue for f32 {
+  fn into_value(self) -> Value {
+    Value::Float(self.into())
+  }
+}
+
+impl FromValue for String {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::String(s) => Ok(s),
+      value => Err(ConversionError::mismatch("String", &value))
+    }
+  }
+}
+
+impl IntoValue for String {
+  fn into_value(self) -> Value {
+    Value::String(self)
+  }
+}
+
+impl IntoValue for &str {
+  fn into_value(self) -> Value {
+    Value::String(self.to_owned())
+  }
+}
+
+/// `None` when null or missing from a map.
+impl<T: FromValue> FromValue for Option<T> {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Null => Ok(None),
+      value => T::from_value(value).map(Some)
+    }
+  }
+
+  fn from_missing() -> Option<Self> {
+    Some(None)
+  }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+  fn into_value(self) -> Value {
+    self.map_or(Value::Null, IntoValue::into_value)
+  }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::List(xs) => xs
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| T::from_value(x).map_err(|e| e.in_index(i)))
+        .collect(),
+      value => Err(ConversionError::mismatch("List", &value))
+    }
+  }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+  fn into_value(self) -> Value {
+    Value::List(self.into_iter().map(IntoValue::into_value).collect())
+  }
+}
+
+impl<T: FromValue> FromValue for HashMap<String, T> {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Map(ps) => ps
+        .into_iter()
+        .map(|(k, v)| match T::from_value(v) {
+          Ok(v) => Ok((k, v)),
+          Err(e) => Err(e.in_key(&k))
+        })
+        .collect(),
+      value => Err(ConversionError::mismatch("Map", &value))
+    }
+  }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+  fn into_value(self) -> Value {
+    Value::Map(self.into_iter().map(|(k, v)| (k, v.into_value())).collect())
+  }
+}
+
+macro_rules! struct_conversions {
+  ($($t:ident),*) => {$(
+    impl FromValue for $t {
+      fn from_value(value: Value) -> Result<Self, ConversionError> {
+        match value {
+          Value::Struct(Struct::$t(x)) => Ok(x),
+          value => Err(ConversionError::mismatch(stringify!($t), &value))
+        }
+      }
+    }
+
+    impl IntoValue for $t {
+      fn into_value(self) -> Value {
+        Value::Struct(Struct::$t(self))
+      }
+    }
+  )*}
+}
+
+struct_conversions!(Node, Relationship, Path, UnboundRelationship);
+struct_conversions!(Date, Time, LocalTime, DateTime, DateTimeZoneId, LegacyDateTime,
+                    LegacyDateTimeZoneId, LocalDateTime, Duration);
+struct_conversions!(Point2D, Point3D);
+
+impl FromValue for Struct {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Struct(x) => Ok(x),
+      value => Err(ConversionError::mismatch("Struct", &value))
+    }
+  }
+}
+
+impl IntoValue for Struct {
+  fn into_value(self) -> Value {
+    Value::Struct(self)
+  }
+}
+
+macro_rules! value_from {
+  ($($t:ty),*) => {$(
+    impl From<$t> for Value {
+      fn from(x: $t) -> Self {
+        x.into_value()
+      }
+    }
+  )*}
+}
+
+value_from!(bool, i8, i16, i32, i64, u8, u16, u32, f32, f64, String, &str);
+value_from!(Node, Relationship, Path, UnboundRelationship, Struct);
+value_from!(Date, Time, LocalTime, DateTime, DateTimeZoneId, LegacyDateTime,
+            LegacyDateTimeZoneId, LocalDateTime, Duration);
+value_from!(Point2D, Point3D);
+
+impl<T: IntoValue> From<Option<T>> for Value {
+  fn from(x: Option<T>) -> Self {
+    x.into_value()
+  }
+}
+
+impl<T: IntoValue> From<Vec<T>> for Value {
+  fn from(x: Vec<T>) -> Self {
+    x.into_value()
+  }
+}
+
+impl<T: IntoValue> From<HashMap<String, T>> for Value {
+  fn from(x: HashMap<String, T>) -> Self {
+    x.into_value()
+  }
+}
+
+macro_rules! try_from_value {
+  ($($t:ty),*) => {$(
+    impl TryFrom<Value> for $t {
+      type Error = ConversionError;
+
+      fn try_from(value: Value) -> Result<Self, Self::Error> {
+        <$t>::from_value(value)
+      }
+    }
+  )*}
+}
+
+try_from_value!(bool, i8, i16, i32, i64, u8, u16, u32, u64, isize, usize, f32, f64, String);
+try_from_value!(Node, Relationship, Path, UnboundRelationship, Struct);
+try_from_value!(Date, Time, LocalTime, DateTime, DateTimeZoneId, LegacyDateTime,
+                LegacyDateTimeZoneId, LocalDateTime, Duration);
+try_from_value!(Point2D, Point3D);
+
+#[cfg(test)]
+mod tests {
+  use packstream_proc::{FromMap, IntoMap};
+  use super::*;
+
+  #[derive(Debug, Default, PartialEq, FromMap, IntoMap)]
+  struct Address {
+    city: String
+  }
+
+  #[derive(Debug, PartialEq, FromMap, IntoMap)]
+  struct Person {
+    name: String,
+    #[map(rename = "yearOfBirth")]
+    born: i64,
+    email: Option<String>,
+    #[map(default)]
+    tags: Vec<String>,
+    #[map(skip)]
+    visits: u32,
+    address: Address
+  }
+
+  fn person() -> HashMap<String, Value> {
+    hashmap! {
+      "name".to_owned() => "Alice".into_value(),
+      "yearOfBirth".to_owned() => Value::Int(1990),
+      "email".to_owned() => "alice@example.com".into_value(),
+      "tags".to_owned() => vec!["admin"].into_value(),
+      "visits".to_owned() => Value::Int(3),
+      "address".to_owned() => hashmap! { "city".to_owned() => "Paris" }.into_value()
+    }
+  }
+
+  #[test]
+  fn derives_from_map() {
+    assert_eq!(Person::from_map(person()), Ok(Person {
+      name: "Alice".to_owned(),
+      born: 1990,
+      email: Some("alice@example.com".to_owned()),
+      tags: vec!["admin".to_owned()],
+      visits: 0,
+      address: Address { city: "Paris".to_owned() }
+    }));
+    assert_eq!(Person::from_value(Value::Map(person())), Person::from_map(person()));
+  }
+
+  #[test]
+  fn defaults_missing_and_null_keys() {
+    let without = || {
+      let mut map = person();
+      map.remove("email");
+      map.remove("tags");
+      map
+    };
+    let missing = Person::from_map(without()).unwrap();
+    assert_eq!((missing.email, missing.tags), (None, vec![]));
+
+    let mut map = without();
+    map.insert("email".to_owned(), Value::Null);
+    map.insert("tags".to_owned(), Value::Null);
+    let null = Person::from_map(map).unwrap();
+    assert_eq!((null.email, null.tags), (None, vec![]));
+  }
+
+  #[test]
+  fn reports_where_map_conversions_fail() {
+    let mut map = person();
+    map.remove("yearOfBirth");
+    assert_eq!(Person::from_map(map), Err(ConversionError::MissingKey("yearOfBirth".to_owned())));
+
+    let mut map = person();
+    map.insert("born".to_owned(), Value::Int(1990));
+    map.remove("yearOfBirth");
+    assert_eq!(Person::from_map(map), Err(ConversionError::MissingKey("yearOfBirth".to_owned())));
+
+    let mut map = person();
+    map.insert("name".to_owned(), Value::Null);
+    assert_eq!(
+      Person::from_map(map),
+      Err(ConversionError::TypeMismatch { expected: "String", found: "Null" }.in_key("name"))
+    );
+
+    let mut map = person();
+    map.insert("address".to_owned(), hashmap! { "city".to_owned() => 75 }.into_value());
+    assert_eq!(
+      Person::from_map(map),
+      Err(ConversionError::TypeMismatch { expected: "String", found: "Int" }.in_key("city").in_key("address"))
+    );
+
+    let mut map = person();
+    map.insert("tags".to_owned(), vec!["admin".into_value(), Value::Int(1)].into_value());
+    assert_eq!(
+      Person::from_map(map),
+      Err(ConversionError::TypeMismatch { expected: "String", found: "Int" }.in_index(1).in_key("tags"))
+    );
+  }
+
+  #[test]
+  fn derives_into_map() {
+    let person = |visits| Person {
+      name: "Alice".to_owned(),
+      born: 1990,
+      email: None,
+      tags: vec!["admin".to_owned()],
+      visits,
+      address: Address { city: "Paris".to_owned() }
+    };
+    let map = person(3).into_map();
+    assert_eq!(map.get("yearOfBirth"), Some(&Value::Int(1990)));
+    assert_eq!(map.get("email"), Some(&Value::Null));
+    assert!(!map.contains_key("born"));
+    assert!(!map.contains_key("visits"));
+    assert_eq!(map.get("address"), Some(&hashmap! { "city".to_owned() => "Paris" }.into_value()));
+    assert_eq!(Person::from_map(map), Ok(person(0)));
+  }
+
+  #[test]
+  fn converts_floats_to_f32() {
+    assert_eq!(f32::from_value(Value::Float(0.5)), Ok(0.5));
+    assert_eq!(f32::from_value(Value::Float(f64::INFINITY)), Ok(f32::INFINITY));
+    assert!(f32::from_value(Value::Float(f64::NAN)).unwrap().is_nan());
+    assert_eq!(
+      f32::from_value(Value::Float(1e39)),
+      Err(ConversionError::FloatOutOfRange { expected: "f32", value: 1e39 })
+    );
+  }
+
+  #[test]
+  fn narrows_integers() {
+    assert_eq!(u8::try_from(Value::Int(255)), Ok(255));
+    assert_eq!(i8::try_from(Value::Int(-128)), Ok(-128));
+    assert_eq!(u8::try_from(Value::Int(256)), Err(ConversionError::OutOfRange { expected: "u8", value: 256 }));
+    assert_eq!(u64::try_from(Value::Int(-1)), Err(ConversionError::OutOfRange { expected: "u64", value: -1 }));
+    assert_eq!(i32::try_from(Value::Int(i64::MAX)),
+               Err(ConversionError::OutOfRange { expected: "i32", value: i64::MAX }));
+    assert_eq!(Value::from(u32::MAX), Value::Int(4_294_967_295));
+  }
+
+  #[test]
+  fn rejects_other_types() {
+    assert_eq!(i64::try_from(Value::Float(1.0)),
+               Err(ConversionError::TypeMismatch { expected: "Int", found: "Float" }));
+    assert_eq!(String::try_from(Value::Null),
+               Err(ConversionError::TypeMismatch { expected: "String", found: "Null" }));
+    assert_eq!(Node::try_from(Value::from(Path { nodes: vec![], relationships: vec![], sequence: vec![] })),
+               Err(ConversionError::TypeMismatch { expected: "Node", found: "Path" }));
+    assert_eq!(Struct::try_from(Value::Boolean(true)),
+               Err(ConversionError::TypeMismatch { expected: "Struct", found: "Boolean" }));
+  }
+
+  #[test]
+  fn converts_options() {
+    assert_eq!(Option::<i64>::from_value(Value::Null), Ok(None));
+    assert_eq!(Option::<i64>::from_value(Value::Int(1)), Ok(Some(1)));
+    assert_eq!(Option::<i64>::from_value(Value::from("1")),
+               Err(ConversionError::TypeMismatch { expected: "Int", found: "String" }));
+    assert_eq!(Value::from(None::<i64>), Value::Null);
+    assert_eq!(Value::from(Some(true)), Value::Boolean(true));
+  }
+
+  #[test]
+  fn converts_collections() {
+    let value = Value::from(vec![vec![1, 2], vec![3]]);
+    assert_eq!(value, Value::List(vec![
+      Value::List(vec![Value::Int(1), Value::Int(2)]),
+      Value::List(vec![Value::Int(3)])
+    ]));
+    assert_eq!(Vec::<Vec<u8>>::from_value(value), Ok(vec![vec![1, 2], vec![3]]));
+    assert_eq!(
+      Vec::<Vec<u8>>::from_value(Value::from(vec![vec![1], vec![2, 300]])),
+      Err(ConversionError::OutOfRange { expected: "u8", value: 300 }.in_index(1).in_index(1))
+    );
+
+    let map = hashmap! { "a".to_owned() => vec![Some(1), None] };
+    let value = Value::from(map.clone());
+    assert_eq!(HashMap::<String, Vec<Option<i64>>>::from_value(value), Ok(map));
+    assert_eq!(
+      HashMap::<String, Vec<i64>>::from_value(Value::from(hashmap! { "a".to_owned() => vec![None, Some(1)] })),
+      Err(ConversionError::TypeMismatch { expected: "Int", found: "Null" }.in_index(0).in_key("a"))
+    );
+    assert_eq!(Vec::<i64>::from_value(Value::Map(HashMap::new())),
+               Err(ConversionError::TypeMismatch { expected: "List", found: "Map" }));
+  }
+
+  #[test]
+  fn displays_error_paths() {
+    let e = ConversionError::OutOfRange { expected: "u8", value: 300 }.in_index(1).in_key("ids");
+    assert_eq!(e.to_string(), "in key \"ids\": at index 1: 300 is out of range of u8");
+  }
+}
@@ -0,0 +1,586 @@
+//! Values written and read as Cypher literals.
+//!
+//! [Value] displays as the Cypher literal of the value, graph structures as
+//! the pattern matching them:
+//!
+//! ```ignore
+//! let person = value!({"name": "O'Hara", "tags": ["a"], "born": 1970});
+//! assert_eq!(person.to_string(), "{born: 1970, name: 'O\\'Hara', tags: ['a']}");
+//! // (:Person:Actor {name: 'Alice'})-[:KNOWS {since: 2001}]->(:Person {name: 'Bob'})
+//! println!("{}", path);
+//! ```
+//!
+//! Map keys are sorted, and quoted with backticks when they are not plain
+//! identifiers. Non-finite floats are written as the quotients giving them,
+//! `0.0/0.0`, `1.0/0.0` and `-1.0/0.0`. Temporal values are written as calls
+//! of the temporal functions, such as `date('2021-03-04')`, date times with
+//! a zone id known by their instant as
+//! `datetime({epochSeconds: 1614830767, nanosecond: 0, timezone: 'Europe/Paris'})`.
+//! Points are written as calls of `point`, such as
+//! `point({srid: 4326, x: 2.35, y: 48.85})`.
+//! Other structures have no Cypher counterpart: unknown ones are written as
+//! `Struct<0x20>(fields)` and registered ones with their `Debug`
+//! representation.
+//!
+//! Literals parse back to values with `str::parse`, which reads null,
+//! booleans, numbers, strings, lists and maps, along with the quotients
+//! written for non-finite floats:
+//!
+//! ```ignore
+//! let params: Value = "{name: 'Alice', ids: [1, 2, 0x1F], ratio: 1.5e3}".parse()?;
+//! ```
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use crate::structs::{Struct, Node, Relationship, Path, UnboundRelationship};
+use crate::value::Value;
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Null       => f.write_str("null"),
+      Value::Boolean(b) => write!(f, "{}", b),
+      Value::Int(n)     => write!(f, "{}", n),
+      Value::Float(n)   => write_float(f, *n),
+      Value::String(cs) => write_string(f, cs),
+      Value::List(xs)   => write_list(f, xs),
+      Value::Map(ps)    => write_map(f, ps),
+      Value::Struct(s)  => write!(f, "{}", s)
+    }
+  }
+}
+
+impl fmt::Display for Struct {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Struct::Node(x)                => write!(f, "{}", x),
+      Struct::Relationship(x)        => write!(f, "{}", x),
+      Struct::Path(x)                => write!(f, "{}", x),
+      Struct::UnboundRelationship(x) => write!(f, "{}", x),
+      Struct::Date(x)                => write!(f, "date('{}')", x),
+      Struct::Time(x)                => write!(f, "time('{}')", x),
+      Struct::LocalTime(x)           => write!(f, "localtime('{}')", x),
+      Struct::DateTime(x)            => write!(f, "datetime('{}')", x),
+      Struct::LegacyDateTime(x)      => write!(f, "datetime('{}')", x),
+      Struct::LegacyDateTimeZoneId(x) => write!(f, "datetime('{}')", x),
+      Struct::DateTimeZoneId(x) => {
+        write!(f, "datetime({{epochSeconds: {}, nanosecond: {}, timezone: ", x.seconds, x.nanoseconds)?;
+        write_string(f, &x.tz_id)?;
+        f.write_str("})")
+      },
+      Struct::LocalDateTime(x)       => write!(f, "localdatetime('{}')", x),
+      Struct::Duration(x)            => write!(f, "duration('{}')", x),
+      Struct::Point2D(x) => {
+        write!(f, "point({{srid: {}, x: ", x.srid)?;
+        write_float(f, x.x)?;
+        f.write_str(", y: ")?;
+        write_float(f, x.y)?;
+        f.write_str("})")
+      },
+      Struct::Point3D(x) => {
+        write!(f, "point({{srid: {}, x: ", x.srid)?;
+        write_float(f, x.x)?;
+        f.write_str(", y: ")?;
+        write_float(f, x.y)?;
+        f.write_str(", z: ")?;
+        write_float(f, x.z)?;
+        f.write_str("})")
+      },
+      Struct::Unknown { signature, fields } => {
+        write!(f, "Struct<{:#04X}>(", signature)?;
+        write_items(f, fields)?;
+        f.write_char(')')
+      },
+      Struct::Custom { value, .. } => write!(f, "{:?}", value)
+    }
+  }
+}
+
+/// Writes `(:Label {properties})`.
+impl fmt::Display for Node {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_char('(')?;
+    for label in &self.labels {
+      f.write_char(':')?;
+      write_name(f, label)?;
+    }
+    write_properties(f, &self.properties, !self.labels.is_empty())?;
+    f.write_char(')')
+  }
+}
+
+/// Writes `[:TYPE {properties}]`, without the nodes it links.
+impl fmt::Display for Relationship {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_relationship(f, &self.type_name, &self.properties)
+  }
+}
+
+/// Writes `[:TYPE {properties}]`.
+impl fmt::Display for UnboundRelationship {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_relationship(f, &self.type_name, &self.properties)
+  }
+}
+
+/// Writes `(a)-[:TYPE]->(b)<-[:TYPE]-(c)`, entities the sequence points
+/// out of the path being written empty.
+impl fmt::Display for Path {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let write_node = |f: &mut fmt::Formatter, i: i64| {
+      match usize::try_from(i).ok().and_then(|i| self.nodes.get(i)) {
+        Some(node) => write!(f, "{}", node),
+        None => f.write_str("()")
+      }
+    };
+    write_node(f, 0)?;
+    for pair in self.sequence.chunks(2) {
+      if let [r, n] = *pair {
+        let relationship = usize::try_from(r.unsigned_abs()).ok()
+          .and_then(|i| i.checked_sub(1))
+          .and_then(|i| self.relationships.get(i));
+        let (before, after) = if r < 0 { ("<-", "-") } else { ("-", "->") };
+        f.write_str(before)?;
+        match relationship {
+          Some(x) => write!(f, "{}", x)?,
+          None => f.write_str("[]")?
+        }
+        f.write_str(after)?;
+        write_node(f, n)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+fn write_float(f: &mut fmt::Formatter, n: f64) -> fmt::Result {
+  if n.is_nan() {
+    f.write_str("0.0/0.0")
+  }
+  else if n.is_infinite() {
+    f.write_str(if n > 0.0 { "1.0/0.0" } else { "-1.0/0.0" })
+  }
+  else {
+    // Debug keeps the decimal point of integral floats.
+    write!(f, "{:?}", n)
+  }
+}
+
+fn write_string(f: &mut fmt::Formatter, cs: &str) -> fmt::Result {
+  f.write_char('\'')?;
+  for c in cs.chars() {
+    match c {
+      '\'' => f.write_str("\\'")?,
+      '\\' => f.write_str("\\\\")?,
+      '\n' => f.write_str("\\n")?,
+      '\r' => f.write_str("\\r")?,
+      '\t' => f.write_str("\\t")?,
+      '\u{8}' => f.write_str("\\b")?,
+      '\u{c}' => f.write_str("\\f")?,
+      c if c.is_control() => write!(f, "\\u{:04X}", c as u32)?,
+      c => f.write_char(c)?
+    }
+  }
+  f.write_char('\'')
+}
+
+fn write_items(f: &mut fmt::Formatter, xs: &[Value]) -> fmt::Result {
+  for (i, x) in xs.iter().enumerate() {
+    if i > 0 {
+      f.write_str(", ")?;
+    }
+    write!(f, "{}", x)?;
+  }
+  Ok(())
+}
+
+fn write_list(f: &mut fmt::Formatter, xs: &[Value]) -> fmt::Result {
+  f.write_char('[')?;
+  write_items(f, xs)?;
+  f.write_char(']')
+}
+
+fn write_map(f: &mut fmt::Formatter, ps: &HashMap<String, Value>) -> fmt::Result {
+  let mut entries = ps.iter().collect::<Vec<_>>();
+  entries.sort_by(|a, b| a.0.cmp(b.0));
+  f.write_char('{')?;
+  for (i, (k, v)) in entries.into_iter().enumerate() {
+    if i > 0 {
+      f.write_str(", ")?;
+    }
+    write_name(f, k)?;
+    write!(f, ": {}", v)?;
+  }
+  f.write_char('}')
+}
+
+/// Writes the properties of a graph entity, if any, after a space when it
+/// follows a label or type.
+fn write_properties(f: &mut fmt::Formatter, ps: &HashMap<String, Value>, space: bool) -> fmt::Result {
+  if ps.is_empty() {
+    return Ok(());
+  }
+  if space {
+    f.write_char(' ')?;
+  }
+  write_map(f, ps)
+}
+
+fn write_relationship(f: &mut fmt::Formatter, type_name: &str,
+                      ps: &HashMap<String, Value>) -> fmt::Result {
+  f.write_str("[:")?;
+  write_name(f, type_name)?;
+  write_properties(f, ps, true)?;
+  f.write_char(']')
+}
+
+fn is_identifier(name: &str) -> bool {
+  let mut chars = name.chars();
+  matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+    && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Writes a key, label or type, quoted with backticks unless it is a plain
+/// identifier.
+fn write_name(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+  if is_identifier(name) {
+    f.write_str(name)
+  }
+  else {
+    write!(f, "`{}`", name.replace('`', "``"))
+  }
+}
+
+/// Error reading a Cypher literal, positions being byte offsets in the input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+  UnexpectedEnd,
+  UnexpectedChar { position: usize, found: char },
+  InvalidNumber { position: usize },
+  InvalidEscape { position: usize }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+      ParseError::UnexpectedChar { position, found } =>
+        write!(f, "unexpected {:?} at {}", found, position),
+      ParseError::InvalidNumber { position } => write!(f, "invalid number at {}", position),
+      ParseError::InvalidEscape { position } => write!(f, "invalid escape sequence at {}", position)
+    }
+  }
+}
+
+impl Error for ParseError {}
+
+impl FromStr for Value {
+  type Err = ParseError;
+
+  /// Parses a Cypher literal, see the [module documentation](self).
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parser = Parser { input: s, position: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+      None => Ok(value),
+      Some(c) => Err(parser.unexpected(c))
+    }
+  }
+}
+
+struct Parser<'a> {
+  input: &'a str,
+  position: usize
+}
+
+impl Parser<'_> {
+  fn peek(&self) -> Option<char> {
+    self.input[self.position..].chars().next()
+  }
+
+  fn next(&mut self) -> Result<char, ParseError> {
+    let c = self.peek().ok_or(ParseError::UnexpectedEnd)?;
+    self.position += c.len_utf8();
+    Ok(c)
+  }
+
+  fn unexpected(&self, found: char) -> ParseError {
+    ParseError::UnexpectedChar { position: self.position, found }
+  }
+
+  fn skip_whitespace(&mut self) {
+    while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+      self.position += self.peek().map_or(0, char::len_utf8);
+    }
+  }
+
+  fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some(c) if c == expected => self.next().map(|_| ()),
+      Some(c) => Err(self.unexpected(c)),
+      None => Err(ParseError::UnexpectedEnd)
+    }
+  }
+
+  /// Consumes `expected` if it comes next, whitespace skipped.
+  fn accept(&mut self, expected: char) -> bool {
+    self.skip_whitespace();
+    let found = self.peek() == Some(expected);
+    if found {
+      self.position += expected.len_utf8();
+    }
+    found
+  }
+
+  fn value(&mut self) -> Result<Value, ParseError> {
+    self.skip_whitespace();
+    match self.peek() {
+      None => Err(ParseError::UnexpectedEnd),
+      Some('[') => self.list().map(Value::List),
+      Some('{') => self.map().map(Value::Map),
+      Some(c @ ('\'' | '"')) => self.string(c).map(Value::String),
+      Some(c) if c == '-' || c == '.' || c.is_ascii_digit() => self.quotient(),
+      Some(c) if c.is_alphabetic() => {
+        let start = self.position;
+        let word = self.identifier();
+        match word.to_ascii_lowercase().as_str() {
+          "null" => Ok(Value::Null),
+          "true" => Ok(Value::Boolean(true)),
+          "false" => Ok(Value::Boolean(false)),
+          _ => Err(ParseError::UnexpectedChar { position: start, found: c })
+        }
+      },
+      Some(c) => Err(self.unexpected(c))
+    }
+  }
+
+  /// Parses a number, or one of the quotients `0.0/0.0`, `1.0/0.0` and
+  /// `-1.0/0.0` written for non-finite floats.
+  fn quotient(&mut self) -> Result<Value, ParseError> {
+    let start = self.position;
+    let dividend = self.number()?;
+    let dividend_end = self.position;
+    self.skip_whitespace();
+    let slash = self.position;
+    if !self.accept('/') {
+      return Ok(dividend);
+    }
+    self.skip_whitespace();
+    let divisor_start = self.position;
+    self.number()?;
+    let divisor = &self.input[divisor_start..self.position];
+    match (&self.input[start..dividend_end], divisor) {
+      ("0.0", "0.0") => Ok(Value::Float(f64::NAN)),
+      ("1.0", "0.0") => Ok(Value::Float(f64::INFINITY)),
+      ("-1.0", "0.0") => Ok(Value::Float(f64::NEG_INFINITY)),
+      _ => Err(ParseError::UnexpectedChar { position: slash, found: '/' })
+    }
+  }
+
+  fn number(&mut self) -> Result<Value, ParseError> {
+    let start = self.position;
+    let rest = &self.input[start..];
+    let digits = rest.strip_prefix('-').unwrap_or(rest);
+    let invalid = ParseError::InvalidNumber { position: start };
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+      let len = hex.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(hex.len());
+      let sign = if digits.len() < rest.len() { "-" } else { "" };
+      self.position += rest.len() - hex.len() + len;
+      return i64::from_str_radix(&format!("{}{}", sign, &hex[..len]), 16)
+        .map(Value::Int)
+        .map_err(|_| invalid);
+    }
+    let mut is_float = false;
+    let mut end = rest.len() - digits.len();
+    let bytes = rest.as_bytes();
+    let skip_digits = |mut i: usize| {
+      while matches!(bytes.get(i), Some(b) if b.is_ascii_digit()) {
+        i += 1;
+      }
+      i
+    };
+    end = skip_digits(end);
+    if bytes.get(end) == Some(&b'.') && matches!(bytes.get(end + 1), Some(b) if b.is_ascii_digit()) {
+      is_float = true;
+      end = skip_digits(end + 1);
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+      let mut exponent = end + 1;
+      if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+        exponent += 1;
+      }
+      if matches!(bytes.get(exponent), Some(b) if b.is_ascii_digit()) {
+        is_float = true;
+        end = skip_digits(exponent);
+      }
+    }
+    self.position += end;
+    let literal = &rest[..end];
+    if is_float {
+      literal.parse().map(Value::Float).map_err(|_| invalid)
+    }
+    else {
+      literal.parse().map(Value::Int).map_err(|_| invalid)
+    }
+  }
+
+  fn string(&mut self, quote: char) -> Result<String, ParseError> {
+    self.next()?;
+    let mut string = String::new();
+    loop {
+      let position = self.position;
+      match self.next()? {
+        c if c == quote => return Ok(string),
+        '\\' => string.push(match self.next()? {
+          '\\' => '\\',
+          '\'' => '\'',
+          '"' => '"',
+          'n' => '\n',
+          'r' => '\r',
+          't' => '\t',
+          'b' => '\u{8}',
+          'f' => '\u{c}',
+          'u' => self.unicode(4, position)?,
+          'U' => self.unicode(8, position)?,
+          _ => return Err(ParseError::InvalidEscape { position })
+        }),
+        c => string.push(c)
+      }
+    }
+  }
+
+  fn unicode(&mut self, len: usize, position: usize) -> Result<char, ParseError> {
+    let invalid = ParseError::InvalidEscape { position };
+    let hex = self.input.get(self.position..self.position + len).ok_or(invalid.clone())?;
+    self.position += len;
+    u32::from_str_radix(hex, 16)
+      .ok()
+      .and_then(char::from_u32)
+      .ok_or(invalid)
+  }
+
+  fn identifier(&mut self) -> &str {
+    let rest = &self.input[self.position..];
+    let len = rest
+      .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+      .unwrap_or(rest.len());
+    self.position += len;
+    &rest[..len]
+  }
+
+  fn key(&mut self) -> Result<String, ParseError> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some('`') => {
+        self.next()?;
+        let mut key = String::new();
+        loop {
+          match self.next()? {
+            '`' if self.peek() == Some('`') => {
+              self.next()?;
+              key.push('`');
+            },
+            '`' => return Ok(key),
+            c => key.push(c)
+          }
+        }
+      },
+      Some(c) if c.is_alphabetic() || c == '_' => Ok(self.identifier().to_owned()),
+      Some(c) => Err(self.unexpected(c)),
+      None => Err(ParseError::UnexpectedEnd)
+    }
+  }
+
+  fn list(&mut self) -> Result<Vec<Value>, ParseError> {
+    self.expect('[')?;
+    let mut xs = Vec::new();
+    if self.accept(']') {
+      return Ok(xs);
+    }
+    loop {
+      xs.push(self.value()?);
+      if self.accept(']') {
+        return Ok(xs);
+      }
+      self.expect(',')?;
+    }
+  }
+
+  fn map(&mut self) -> Result<HashMap<String, Value>, ParseError> {
+    self.expect('{')?;
+    let mut ps = HashMap::new();
+    if self.accept('}') {
+      return Ok(ps);
+    }
+    loop {
+      let key = self.key()?;
+      self.expect(':')?;
+      ps.insert(key, self.value()?);
+      if self.accept('}') {
+        return Ok(ps);
+      }
+      self.expect(',')?;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn map(entries: &[(&str, Value)]) -> Value {
+    Value::Map(entries.iter().map(|(k, v)| ((*k).to_owned(), v.clone())).collect())
+  }
+
+  fn round_trip(value: Value) {
+    let literal = value.to_string();
+    assert_eq!(literal.parse::<Value>(), Ok(value), "{}", literal);
+  }
+
+  #[test]
+  fn round_trips_literals() {
+    let values = vec![
+      Value::Null,
+      Value::Boolean(true),
+      Value::Int(0),
+      Value::Int(i64::MIN),
+      Value::Int(i64::MAX),
+      Value::Float(1.0),
+      Value::Float(-0.5),
+      Value::Float(1e300),
+      Value::Float(5e-324),
+      Value::Float(f64::INFINITY),
+      Value::Float(f64::NEG_INFINITY),
+      Value::String(String::new()),
+      Value::String("O'Hara \\ \"quoted\"\n\t\u{1} é".to_owned()),
+      Value::List(vec![Value::Int(1), Value::List(vec![]), Value::Null]),
+      map(&[
+        ("name", Value::String("Alice".to_owned())),
+        ("first name", Value::Int(1)),
+        ("`tick`", Value::Float(f64::INFINITY)),
+        ("nested", map(&[("list", Value::List(vec![Value::Boolean(false)]))]))
+      ])
+    ];
+    values.into_iter().for_each(round_trip);
+  }
+
+  #[test]
+  fn round_trips_nan() {
+    assert_eq!(Value::Float(f64::NAN).to_string(), "0.0/0.0");
+    assert!(matches!("0.0 / 0.0".parse(), Ok(Value::Float(n)) if n.is_nan()));
+  }
+
+  #[test]
+  fn rejects_other_quotients() {
+    assert_eq!("1/2".parse::<Value>(), Err(ParseError::UnexpectedChar { position: 1, found: '/' }));
+    assert_eq!("[2.0 / 0.0]".parse::<Value>(),
+               Err(ParseError::UnexpectedChar { position: 5, found: '/' }));
+    assert_eq!("1.0/1.0".parse::<Value>(),
+               Err(ParseError::UnexpectedChar { position: 3, found: '/' }));
+    assert_eq!("1.0/".parse::<Value>(), Err(ParseError::InvalidNumber { position: 4 }));
+  }
+}
@@ -0,0 +1,607 @@
+//! Conversions between [Value] and `serde_json::Value`, enabled by the
+//! `json` feature.
+//!
+//! Null, booleans, strings, lists and maps have their JSON counterpart.
+//! Integers and floats become JSON numbers, which keep them apart: `1` is
+//! read back as `Int(1)` and `1.0` as `Float(1.0)`. Integers too large for
+//! `i64` are read as floats.
+//!
+//! Structures have no JSON counterpart and are written as chosen by a
+//! [JsonFormat]:
+//!
+//! | Value                 | [Fields](JsonFormat::Fields)                                       | [Properties](JsonFormat::Properties) |
+//! |-----------------------|--------------------------------------------------------------------|--------------------------------------|
+//! | `Node`                | `{"id", "labels", "properties"}`                                   | properties                           |
+//! | `Relationship`        | `{"id", "start_node_id", "end_node_id", "type", "properties"}`     | properties                           |
+//! | `UnboundRelationship` | `{"id", "type", "properties"}`                                     | properties                           |
+//! | `Path`                | `{"nodes", "relationships", "sequence"}`, entities as fields       | properties of the nodes and relationships, in path order |
+//! | temporal structures   | `{"days"}`, `{"seconds", "nanoseconds", "tz_offset_seconds"}`, ... | ISO 8601 string                      |
+//! | points                | `{"srid", "x", "y"}`, `{"srid", "x", "y", "z"}`                    | same as fields                       |
+//! | other structures      | `{"signature", "fields"}`                                          | fields                               |
+//!
+//! Entities sent by Bolt 5 servers also have their `"element_id"`, and
+//! relationships their `"start_node_element_id"` and `"end_node_element_id"`.
+//!
+//! Both lose the type of structures, and non-finite floats are written as
+//! `null`. [Tagged](JsonFormat::Tagged) is lossless: structures are written
+//! as their fields wrapped in a single-key object naming their type, and
+//! [from_json](Value::from_json) reads them back:
+//!
+//! ```ignore
+//! let json = value.to_json(JsonFormat::Tagged);
+//! // {"$node": {"id": 1, "labels": ["Person"], "properties": {"name": "Alice"}}}
+//! assert_eq!(Value::from_json(json, JsonFormat::Tagged)?, value);
+//! ```
+//!
+//! The tags are `$node`, `$relationship`, `$unbound_relationship`, `$path`,
+//! `$struct`, and for temporal structures `$date`, `$time`, `$local_time`,
+//! `$date_time`, `$date_time_zone_id`, `$legacy_date_time`,
+//! `$legacy_date_time_zone_id`, `$local_date_time` and `$duration`, and for
+//! points `$point_2d` and `$point_3d`. Non-finite floats are written as
+//! `{"$float": "NaN"}`, `"Infinity"` or `"-Infinity"`, and maps whose only
+//! key starts with `$` are wrapped in `{"$map": ...}` so as not to be read
+//! as a tag. Registered structures are written as `$struct` and hydrated
+//! back when read, being kept as [Unknown](Struct::Unknown) if their fields
+//! don't match.
+//!
+//! [Value] has no bytes variant: PackStream byte arrays are not unpacked as
+//! values, so there is no bytes representation to choose.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use serde_json::{Map, Number, Value as Json};
+use packstream_core::packer::{Packer, PackValue};
+use packstream_core::unpacker::{Unpacker, UnpackValue};
+use packstream_core::structure::BoltStructure;
+use packstream_core::version::Version;
+use crate::convert::{take, ConversionError};
+use crate::registry;
+use crate::structs::{Struct, Node, Relationship, Path, UnboundRelationship};
+use crate::temporal::*;
+use crate::spatial::{Point2D, Point3D};
+use crate::unpacker::unpack_unknown_struct;
+use crate::value::Value;
+
+/// JSON representation of structures, see the [module documentation](self).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum JsonFormat {
+  /// Structures as an object of their fields.
+  #[default]
+  Fields,
+  /// Graph entities as their properties, other structures as their fields.
+  Properties,
+  /// Structures as an object of their fields, wrapped in an object naming
+  /// their type. Lossless.
+  Tagged
+}
+
+impl Value {
+  /// Converts the value to JSON, structures being written as `format` tells.
+  pub fn to_json(&self, format: JsonFormat) -> Json {
+    match self {
+      Value::Null       => Json::Null,
+      Value::Boolean(b) => Json::Bool(*b),
+      Value::Int(n)     => Json::from(*n),
+      Value::Float(n)   => float_to_json(*n, format),
+      Value::String(cs) => Json::String(cs.clone()),
+      Value::List(xs)   => list_to_json(xs, format),
+      Value::Map(ps)    => map_to_json(ps, format),
+      Value::Struct(s)  => struct_to_json(s, format)
+    }
+  }
+
+  /**
+   * Converts JSON to a value. Objects are read as maps, tags included,
+   * unless `format` is [Tagged](JsonFormat::Tagged). Conversion only fails
+   * on malformed tagged structures.
+   */
+  pub fn from_json(json: Json, format: JsonFormat) -> Result<Value, ConversionError> {
+    match format {
+      JsonFormat::Tagged => from_tagged(json),
+      _ => Ok(from_plain(json))
+    }
+  }
+}
+
+/// Converts with [JsonFormat::Fields].
+impl From<Value> for Json {
+  fn from(value: Value) -> Self {
+    value.to_json(JsonFormat::Fields)
+  }
+}
+
+/// Converts objects to maps, tags included.
+impl From<Json> for Value {
+  fn from(json: Json) -> Self {
+    from_plain(json)
+  }
+}
+
+const TAG_NODE: &str = "$node";
+const TAG_RELATIONSHIP: &str = "$relationship";
+const TAG_UNBOUND_RELATIONSHIP: &str = "$unbound_relationship";
+const TAG_PATH: &str = "$path";
+const TAG_STRUCT: &str = "$struct";
+const TAG_FLOAT: &str = "$float";
+const TAG_MAP: &str = "$map";
+
+/// Tag, signature and field names of the structures written as objects of
+/// their fields.
+const NAMED_FIELDS: [(&str, u8, &[&str]); 11] = [
+  ("$date", Date::SIGNATURE, Date::FIELDS),
+  ("$time", Time::SIGNATURE, Time::FIELDS),
+  ("$local_time", LocalTime::SIGNATURE, LocalTime::FIELDS),
+  ("$date_time", DateTime::SIGNATURE, DateTime::FIELDS),
+  ("$date_time_zone_id", DateTimeZoneId::SIGNATURE, DateTimeZoneId::FIELDS),
+  ("$legacy_date_time", LegacyDateTime::SIGNATURE, LegacyDateTime::FIELDS),
+  ("$legacy_date_time_zone_id", LegacyDateTimeZoneId::SIGNATURE, LegacyDateTimeZoneId::FIELDS),
+  ("$local_date_time", LocalDateTime::SIGNATURE, LocalDateTime::FIELDS),
+  ("$duration", Duration::SIGNATURE, Duration::FIELDS),
+  ("$point_2d", Point2D::SIGNATURE, Point2D::FIELDS),
+  ("$point_3d", Point3D::SIGNATURE, Point3D::FIELDS)
+];
+
+fn tagged(tag: &str, json: Json) -> Json {
+  let mut map = Map::new();
+  map.insert(tag.to_owned(), json);
+  Json::Object(map)
+}
+
+fn object<const N: usize>(entries: [(&str, Json); N]) -> Json {
+  Json::Object(IntoIterator::into_iter(entries)
+    .map(|(k, v)| (k.to_owned(), v))
+    .collect())
+}
+
+fn float_to_json(n: f64, format: JsonFormat) -> Json {
+  match Number::from_f64(n) {
+    Some(n) => Json::Number(n),
+    None if format == JsonFormat::Tagged => {
+      let name = if n.is_nan() { "NaN" } else if n > 0.0 { "Infinity" } else { "-Infinity" };
+      tagged(TAG_FLOAT, Json::from(name))
+    },
+    None => Json::Null
+  }
+}
+
+fn list_to_json(xs: &[Value], format: JsonFormat) -> Json {
+  Json::Array(xs.iter().map(|x| x.to_json(format)).collect())
+}
+
+fn map_to_json(ps: &HashMap<String, Value>, format: JsonFormat) -> Json {
+  let json = properties_to_json(ps, format);
+  let is_tag_like = ps.len() == 1 && ps.keys().all(|k| k.starts_with('$'));
+  if format == JsonFormat::Tagged && is_tag_like {
+    tagged(TAG_MAP, json)
+  }
+  else {
+    json
+  }
+}
+
+fn properties_to_json(ps: &HashMap<String, Value>, format: JsonFormat) -> Json {
+  Json::Object(ps
+    .iter()
+    .map(|(k, v)| (k.clone(), v.to_json(format)))
+    .collect())
+}
+
+fn struct_to_json(s: &Struct, format: JsonFormat) -> Json {
+  match (s, format) {
+    (Struct::Node(x), JsonFormat::Properties) => properties_to_json(&x.properties, format),
+    (Struct::Relationship(x), JsonFormat::Properties) => properties_to_json(&x.properties, format),
+    (Struct::UnboundRelationship(x), JsonFormat::Properties) => properties_to_json(&x.properties, format),
+    (Struct::Path(x), JsonFormat::Properties) => Json::Array(path_entities(x, format)),
+    (Struct::Node(x), _) => with_tag(TAG_NODE, node_to_json(x, format), format),
+    (Struct::Relationship(x), _) => with_tag(TAG_RELATIONSHIP, relationship_to_json(x, format), format),
+    (Struct::UnboundRelationship(x), _) =>
+      with_tag(TAG_UNBOUND_RELATIONSHIP, unbound_to_json(x, format), format),
+    (Struct::Path(x), _) => with_tag(TAG_PATH, path_to_json(x, format), format),
+    (Struct::Unknown { signature, fields }, _) => unknown_to_json(*signature, fields, format),
+    (Struct::Custom { .. }, _) => match struct_fields(s) {
+      Some((signature, fields)) => unknown_to_json(signature, &fields, format),
+      None => Json::Null
+    },
+    (Struct::Point2D(_), _) | (Struct::Point3D(_), _) => named_fields_to_json(s, format),
+    (_, JsonFormat::Properties) => Json::String(temporal_string(s)),
+    (_, _) => named_fields_to_json(s, format)
+  }
+}
+
+fn with_tag(tag: &str, json: Json, format: JsonFormat) -> Json {
+  match format {
+    JsonFormat::Tagged => tagged(tag, json),
+    _ => json
+  }
+}
+
+fn node_to_json(x: &Node, format: JsonFormat) -> Json {
+  with_element_ids(object([
+    ("id", Json::from(x.id)),
+    ("labels", Json::from(x.labels.clone())),
+    ("properties", map_to_json(&x.properties, format))
+  ]), [("element_id", &x.element_id)])
+}
+
+fn relationship_to_json(x: &Relationship, format: JsonFormat) -> Json {
+  with_element_ids(object([
+    ("id", Json::from(x.id)),
+    ("start_node_id", Json::from(x.start_node_id)),
+    ("end_node_id", Json::from(x.end_node_id)),
+    ("type", Json::from(x.type_name.clone())),
+    ("properties", map_to_json(&x.properties, format))
+  ]), [
+    ("element_id", &x.element_id),
+    ("start_node_element_id", &x.start_node_element_id),
+    ("end_node_element_id", &x.end_node_element_id)
+  ])
+}
+
+fn unbound_to_json(x: &UnboundRelationship, format: JsonFormat) -> Json {
+  with_element_ids(object([
+    ("id", Json::from(x.id)),
+    ("type", Json::from(x.type_name.clone())),
+    ("properties", map_to_json(&x.properties, format))
+  ]), [("element_id", &x.element_id)])
+}
+
+/// Adds the element ids sent by the server to the object of an entity.
+fn with_element_ids<const N: usize>(mut json: Json, ids: [(&str, &Option<String>); N])
+                                     -> Json {
+  if let Json::Object(map) = &mut json {
+    for (key, id) in IntoIterator::into_iter(ids) {
+      if let Some(id) = id {
+        map.insert(key.to_owned(), Json::from(id.clone()));
+      }
+    }
+  }
+  json
+}
+
+fn path_to_json(x: &Path, format: JsonFormat) -> Json {
+  object([
+    ("nodes", Json::Array(x.nodes.iter().map(|n| node_to_json(n, format)).collect())),
+    ("relationships", Json::Array(x.relationships.iter().map(|r| unbound_to_json(r, format)).collect())),
+    ("sequence", Json::from(x.sequence.clone()))
+  ])
+}
+
+/// Properties of the nodes and relationships of a path, in path order.
+/// Entries of the sequence pointing out of the path are skipped.
+fn path_entities(x: &Path, format: JsonFormat) -> Vec<Json> {
+  let node = |i: i64| usize::try_from(i).ok()
+    .and_then(|i| x.nodes.get(i))
+    .map(|n| properties_to_json(&n.properties, format));
+  let relationship = |i: i64| usize::try_from(i.unsigned_abs()).ok()
+    .and_then(|i| i.checked_sub(1))
+    .and_then(|i| x.relationships.get(i))
+    .map(|r| properties_to_json(&r.properties, format));
+  node(0).into_iter()
+    .chain(x.sequence
+      .chunks(2)
+      .flat_map(|pair| match *pair {
+        [r, n] => vec![relationship(r), node(n)],
+        _ => vec![]
+      })
+      .flatten())
+    .collect()
+}
+
+fn unknown_to_json(signature: u8, fields: &[Value], format: JsonFormat) -> Json {
+  match format {
+    JsonFormat::Properties => list_to_json(fields, format),
+    _ => with_tag(TAG_STRUCT, object([
+      ("signature", Json::from(signature)),
+      ("fields", list_to_json(fields, format))
+    ]), format)
+  }
+}
+
+/// ISO 8601 representation of a temporal structure.
+fn temporal_string(s: &Struct) -> String {
+  match s {
+    Struct::Date(x) => x.to_string(),
+    Struct::Time(x) => x.to_string(),
+    Struct::LocalTime(x) => x.to_string(),
+    Struct::DateTime(x) => x.to_string(),
+    Struct::DateTimeZoneId(x) => x.to_string(),
+    Struct::LegacyDateTime(x) => x.to_string(),
+    Struct::LegacyDateTimeZoneId(x) => x.to_string(),
+    Struct::LocalDateTime(x) => x.to_string(),
+    Struct::Duration(x) => x.to_string(),
+    _ => String::new()
+  }
+}
+
+/// Writes a temporal structure or point as an object of its fields by name.
+fn named_fields_to_json(s: &Struct, format: JsonFormat) -> Json {
+  let (signature, fields) = match struct_fields(s) {
+    Some(x) => x,
+    None => return Json::Null
+  };
+  let entry = NAMED_FIELDS.iter().find(|(_, sign, _)| *sign == signature);
+  let names = entry.map_or(&[][..], |(_, _, names)| *names);
+  let json = Json::Object(names
+    .iter()
+    .zip(&fields)
+    .map(|(name, value)| ((*name).to_owned(), value.to_json(format)))
+    .collect());
+  match entry {
+    Some((tag, _, _)) => with_tag(tag, json, format),
+    None => json
+  }
+}
+
+/// Fields of a structure, read back from its packed form. `None` when it is
+/// a registered structure whose signature is not registered anymore.
+fn struct_fields(s: &Struct) -> Option<(u8, Vec<Value>)> {
+  // Packed as of Bolt 5 for the element ids, date times as themselves so
+  // that they keep their encoding.
+  let mut packer = Packer::with_version(Version::V5_0);
+  match s.clone() {
+    Struct::DateTime(x) => packer.pack(x),
+    Struct::DateTimeZoneId(x) => packer.pack(x),
+    Struct::LegacyDateTime(x) => packer.pack(x),
+    Struct::LegacyDateTimeZoneId(x) => packer.pack(x),
+    s => packer.pack(s)
+  }.ok()?;
+  match unpack_unknown_struct(&mut Unpacker::new(packer.out.buf)) {
+    Ok(Struct::Unknown { signature, fields }) => Some((signature, fields)),
+    _ => None
+  }
+}
+
+fn number_to_value(n: &Number) -> Value {
+  match n.as_i64() {
+    Some(n) => Value::Int(n),
+    None => Value::Float(n.as_f64().unwrap_or(f64::NAN))
+  }
+}
+
+fn from_plain(json: Json) -> Value {
+  match json {
+    Json::Null       => Value::Null,
+    Json::Bool(b)    => Value::Boolean(b),
+    Json::Number(n)  => number_to_value(&n),
+    Json::String(cs) => Value::String(cs),
+    Json::Array(xs)  => Value::List(xs.into_iter().map(from_plain).collect()),
+    Json::Object(ps) => Value::Map(ps
+      .into_iter()
+      .map(|(k, v)| (k, from_plain(v)))
+      .collect())
+  }
+}
+
+fn from_tagged(json: Json) -> Result<Value, ConversionError> {
+  match json {
+    Json::Array(xs) => xs
+      .into_iter()
+      .enumerate()
+      .map(|(i, x)| from_tagged(x).map_err(|e| e.in_index(i)))
+      .collect::<Result<Vec<_>, _>>()
+      .map(Value::List),
+    Json::Object(ps) => {
+      let tag = match ps.iter().next() {
+        Some((k, _)) if ps.len() == 1 && k.starts_with('$') => k.clone(),
+        _ => return object_from_tagged(ps).map(Value::Map)
+      };
+      let inner = ps.into_iter().next().map(|(_, v)| v).unwrap_or(Json::Null);
+      from_tag(&tag, inner).map_err(|e| e.in_key(&tag))
+    },
+    json => Ok(from_plain(json))
+  }
+}
+
+fn object_from_tagged(ps: Map<String, Json>) -> Result<HashMap<String, Value>, ConversionError> {
+  ps.into_iter()
+    .map(|(k, v)| from_tagged(v)
+      .map_err(|e| e.in_key(&k))
+      .map(|v| (k, v)))
+    .collect()
+}
+
+/// Reads the content of a tag. Unknown tags are read as maps.
+fn from_tag(tag: &str, json: Json) -> Result<Value, ConversionError> {
+  match tag {
+    TAG_MAP => fields(json).map(Value::Map),
+    TAG_FLOAT => match json.as_str() {
+      Some("NaN") => Ok(Value::Float(f64::NAN)),
+      Some("Infinity") => Ok(Value::Float(f64::INFINITY)),
+      Some("-Infinity") => Ok(Value::Float(f64::NEG_INFINITY)),
+      _ => Err(ConversionError::TypeMismatch { expected: "Float", found: json_kind(&json) })
+    },
+    TAG_NODE => fields(json).and_then(node).map(Value::from),
+    TAG_RELATIONSHIP => fields(json).and_then(relationship).map(Value::from),
+    TAG_UNBOUND_RELATIONSHIP => fields(json).and_then(unbound).map(Value::from),
+    TAG_PATH => fields(json).and_then(path).map(Value::from),
+    TAG_STRUCT => fields(json).and_then(unknown).map(Value::from),
+    _ => match NAMED_FIELDS.iter().find(|(t, _, _)| *t == tag) {
+      Some((_, signature, names)) => fields(json)
+        .and_then(|fields| named_fields(*signature, names, fields))
+        .map(Value::from),
+      None => from_tagged(json).map(|v| {
+        let mut map = HashMap::new();
+        map.insert(tag.to_owned(), v);
+        Value::Map(map)
+      })
+    }
+  }
+}
+
+/// Reads a temporal structure or point from its fields by name.
+fn named_fields(signature: u8, names: &[&str], mut fields: HashMap<String, Value>)
+            -> Result<Struct, ConversionError> {
+  let fields = names
+    .iter()
+    .map(|name| take(&mut fields, name))
+    .collect::<Result<Vec<Value>, _>>()?;
+  Ok(hydrate(Struct::Unknown { signature, fields }))
+}
+
+fn json_kind(json: &Json) -> &'static str {
+  match json {
+    Json::Null      => "null",
+    Json::Bool(_)   => "boolean",
+    Json::Number(_) => "number",
+    Json::String(_) => "string",
+    Json::Array(_)  => "array",
+    Json::Object(_) => "object"
+  }
+}
+
+fn fields(json: Json) -> Result<HashMap<String, Value>, ConversionError> {
+  match json {
+    Json::Object(ps) => object_from_tagged(ps),
+    json => Err(ConversionError::TypeMismatch { expected: "Map", found: json_kind(&json) })
+  }
+}
+
+fn node(mut fields: HashMap<String, Value>) -> Result<Node, ConversionError> {
+  Ok(Node {
+    id: take(&mut fields, "id")?,
+    labels: take(&mut fields, "labels")?,
+    properties: take(&mut fields, "properties")?,
+    element_id: take(&mut fields, "element_id")?
+  })
+}
+
+fn relationship(mut fields: HashMap<String, Value>) -> Result<Relationship, ConversionError> {
+  Ok(Relationship {
+    id: take(&mut fields, "id")?,
+    start_node_id: take(&mut fields, "start_node_id")?,
+    end_node_id: take(&mut fields, "end_node_id")?,
+    type_name: take(&mut fields, "type")?,
+    properties: take(&mut fields, "properties")?,
+    element_id: take(&mut fields, "element_id")?,
+    start_node_element_id: take(&mut fields, "start_node_element_id")?,
+    end_node_element_id: take(&mut fields, "end_node_element_id")?
+  })
+}
+
+fn unbound(mut fields: HashMap<String, Value>) -> Result<UnboundRelationship, ConversionError> {
+  Ok(UnboundRelationship {
+    id: take(&mut fields, "id")?,
+    type_name: take(&mut fields, "type")?,
+    properties: take(&mut fields, "properties")?,
+    element_id: take(&mut fields, "element_id")?
+  })
+}
+
+fn path(mut fields: HashMap<String, Value>) -> Result<Path, ConversionError> {
+  let nodes: Vec<HashMap<String, Value>> = take(&mut fields, "nodes")?;
+  let relationships: Vec<HashMap<String, Value>> = take(&mut fields, "relationships")?;
+  Ok(Path {
+    nodes: nodes
+      .into_iter()
+      .enumerate()
+      .map(|(i, x)| node(x).map_err(|e| e.in_index(i).in_key("nodes")))
+      .collect::<Result<_, _>>()?,
+    relationships: relationships
+      .into_iter()
+      .enumerate()
+      .map(|(i, x)| unbound(x).map_err(|e| e.in_index(i).in_key("relationships")))
+      .collect::<Result<_, _>>()?,
+    sequence: take(&mut fields, "sequence")?
+  })
+}
+
+/// Reads a structure of any signature, hydrated if registered.
+fn unknown(mut fields: HashMap<String, Value>) -> Result<Struct, ConversionError> {
+  let signature = take(&mut fields, "signature")?;
+  let fields = take(&mut fields, "fields")?;
+  let unknown = Struct::Unknown { signature, fields };
+  if registry::hydrator(signature).is_none() {
+    return Ok(unknown);
+  }
+  Ok(hydrate(unknown))
+}
+
+/// Reads an unknown structure back as the type of its signature, keeping it
+/// unknown if its fields don't match.
+fn hydrate(unknown: Struct) -> Struct {
+  let mut packer = Packer::with_version(Version::V5_0);
+  let hydrated: Result<Struct, _> = packer.pack(unknown.clone())
+    .and_then(|_| Unpacker::new(packer.out.buf).unpack());
+  hydrated.unwrap_or(unknown)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+  use super::*;
+
+  fn properties(entries: &[(&str, Value)]) -> HashMap<String, Value> {
+    entries.iter().map(|(k, v)| ((*k).to_owned(), v.clone())).collect()
+  }
+
+  fn tagged_round_trip(value: Value) {
+    let json = value.to_json(JsonFormat::Tagged);
+    assert_eq!(Value::from_json(json.clone(), JsonFormat::Tagged), Ok(value), "{}", json);
+  }
+
+  #[test]
+  fn round_trips_tagged_values() {
+    let tag_like = properties(&[("$float", Value::String("x".to_owned()))]);
+    let node = Node {
+      id: 1,
+      labels: vec!["Person".to_owned()],
+      properties: tag_like.clone(),
+      element_id: Some("4:db:1".to_owned())
+    };
+    let relationship = Relationship {
+      id: 2,
+      start_node_id: 1,
+      end_node_id: 1,
+      type_name: "KNOWS".to_owned(),
+      properties: tag_like.clone(),
+      element_id: None,
+      start_node_element_id: None,
+      end_node_element_id: None
+    };
+    let path = Path::from_segments(vec![(node.clone(), relationship.clone(), node.clone())]).unwrap();
+    let values = vec![
+      Value::Null,
+      Value::Int(1),
+      Value::Float(1.0),
+      Value::Float(f64::INFINITY),
+      Value::Float(f64::NEG_INFINITY),
+      Value::String("$node".to_owned()),
+      Value::List(vec![Value::Null, Value::Boolean(true)]),
+      Value::Map(tag_like.clone()),
+      Value::Map(properties(&[("$map", Value::Map(tag_like))])),
+      Value::Struct(Struct::Node(node)),
+      Value::Struct(Struct::Relationship(relationship)),
+      Value::Struct(Struct::Path(path)),
+      Value::Struct(Struct::Date(Date { days: 19000 })),
+      Value::Struct(Struct::DateTimeZoneId(DateTimeZoneId {
+        seconds: 1,
+        nanoseconds: 2,
+        tz_id: "Europe/Paris".to_owned()
+      })),
+      Value::Struct(Struct::Duration(Duration { months: 1, days: 2, seconds: 3, nanoseconds: 4 })),
+      Value::Struct(Struct::Point3D(Point3D { srid: 9157, x: 1.0, y: 2.0, z: 3.0 })),
+      Value::Struct(Struct::Unknown { signature: 0x20, fields: vec![Value::Int(1)] })
+    ];
+    values.into_iter().for_each(tagged_round_trip);
+  }
+
+  #[test]
+  fn round_trips_tagged_nan() {
+    let json = Value::Float(f64::NAN).to_json(JsonFormat::Tagged);
+    assert_eq!(json, json!({"$float": "NaN"}));
+    assert!(matches!(Value::from_json(json, JsonFormat::Tagged), Ok(Value::Float(n)) if n.is_nan()));
+  }
+
+  #[test]
+  fn writes_entities_by_format() {
+    let node = Value::Struct(Struct::Node(Node {
+      id: 1,
+      labels: vec![],
+      properties: properties(&[("name", Value::String("Alice".to_owned()))]),
+      element_id: None
+    }));
+    assert_eq!(node.to_json(JsonFormat::Fields),
+               json!({"id": 1, "labels": [], "properties": {"name": "Alice"}}));
+    assert_eq!(node.to_json(JsonFormat::Properties), json!({"name": "Alice"}));
+    assert_eq!(Value::Float(f64::NAN).to_json(JsonFormat::Fields), Json::Null);
+  }
+}
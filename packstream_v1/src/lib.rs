@@ -1,8 +1,24 @@
 #[macro_use(hashmap)]
 extern crate maplit;
+// Lets the derives of packstream_proc be used within this crate.
+extern crate self as packstream_v1;
+
+#[macro_use]
+mod macros;
 
 pub mod packer;
 pub mod unpacker;
 pub mod structs;
+pub mod path;
+pub mod temporal;
+pub mod spatial;
 pub mod value;
 pub mod messages;
+pub mod registry;
+pub mod convert;
+pub mod row;
+pub mod order;
+pub mod cypher;
+pub mod access;
+#[cfg(feature = "json")]
+pub mod json;
@@ -0,0 +1,153 @@
+/// Builds a [Value](crate::value::Value) from a JSON-like literal. Lists and
+/// maps nest, `null` is [Null](crate::value::Value::Null) and any other
+/// expression is converted with `Value::from`:
+///
+/// ```ignore
+/// let name = "Alice";
+/// let person = value!({
+///   "name": name,
+///   "tags": ["a", "b"],
+///   "age": 42,
+///   "meta": null
+/// });
+/// ```
+///
+/// Map keys are string literals or parenthesized expressions, followed by
+/// `:` or `=>`.
+#[macro_export]
+macro_rules! value {
+  (null) => {
+    $crate::value::Value::Null
+  };
+  ([ $($tt:tt)* ]) => {
+    $crate::value::Value::List($crate::__value_list!([] $($tt)*))
+  };
+  ({ $($tt:tt)* }) => {
+    $crate::value::Value::Map({
+      let mut map = ::std::collections::HashMap::new();
+      $crate::__value_map!(map $($tt)*);
+      map
+    })
+  };
+  ($other:expr) => {
+    $crate::value::Value::from($other)
+  };
+}
+
+/// Builds the parameters map of a [Run](crate::messages::Run), keys and
+/// values following the syntax of [value!](crate::value!):
+///
+/// ```ignore
+/// let run = Run {
+///   statement: "MATCH (n) WHERE id(n) = $id SET n.name = $name".into(),
+///   parameters: params! { "id" => 1, "name" => name, "tags" => ["a", "b"] }
+/// };
+/// ```
+#[macro_export]
+macro_rules! params {
+  ($($tt:tt)*) => {{
+    let mut params: ::std::collections::HashMap<::std::string::String, $crate::value::Value> =
+      ::std::collections::HashMap::new();
+    $crate::__value_map!(params $($tt)*);
+    params
+  }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __value_list {
+  ([ $($items:expr,)* ]) => {
+    ::std::vec![$($items),*]
+  };
+  ([ $($items:expr,)* ] null $(, $($rest:tt)*)?) => {
+    $crate::__value_list!([ $($items,)* $crate::value!(null), ] $($($rest)*)?)
+  };
+  ([ $($items:expr,)* ] [ $($list:tt)* ] $(, $($rest:tt)*)?) => {
+    $crate::__value_list!([ $($items,)* $crate::value!([ $($list)* ]), ] $($($rest)*)?)
+  };
+  ([ $($items:expr,)* ] { $($map:tt)* } $(, $($rest:tt)*)?) => {
+    $crate::__value_list!([ $($items,)* $crate::value!({ $($map)* }), ] $($($rest)*)?)
+  };
+  ([ $($items:expr,)* ] $next:expr, $($rest:tt)*) => {
+    $crate::__value_list!([ $($items,)* $crate::value!($next), ] $($rest)*)
+  };
+  ([ $($items:expr,)* ] $last:expr) => {
+    $crate::__value_list!([ $($items,)* $crate::value!($last), ])
+  };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __value_map {
+  (@value $map:ident $key:tt null $(, $($rest:tt)*)?) => {
+    $map.insert(::std::string::String::from($key), $crate::value!(null));
+    $crate::__value_map!($map $($($rest)*)?);
+  };
+  (@value $map:ident $key:tt [ $($list:tt)* ] $(, $($rest:tt)*)?) => {
+    $map.insert(::std::string::String::from($key), $crate::value!([ $($list)* ]));
+    $crate::__value_map!($map $($($rest)*)?);
+  };
+  (@value $map:ident $key:tt { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+    $map.insert(::std::string::String::from($key), $crate::value!({ $($inner)* }));
+    $crate::__value_map!($map $($($rest)*)?);
+  };
+  (@value $map:ident $key:tt $value:expr, $($rest:tt)*) => {
+    $map.insert(::std::string::String::from($key), $crate::value!($value));
+    $crate::__value_map!($map $($rest)*);
+  };
+  (@value $map:ident $key:tt $value:expr) => {
+    $map.insert(::std::string::String::from($key), $crate::value!($value));
+  };
+  ($map:ident) => {};
+  ($map:ident $key:tt : $($rest:tt)*) => {
+    $crate::__value_map!(@value $map $key $($rest)*);
+  };
+  ($map:ident $key:tt => $($rest:tt)*) => {
+    $crate::__value_map!(@value $map $key $($rest)*);
+  };
+}
+
+/// Builds a path for [get_path](crate::value::Value::get_path) from names
+/// and indexes:
+///
+/// ```ignore
+/// let city = value.get_path(&keys!["address", 0, "city"]);
+/// ```
+#[macro_export]
+macro_rules! keys {
+  ($($key:expr),* $(,)?) => {
+    [$($crate::access::Key::from($key)),*]
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use crate::value::Value;
+
+  #[test]
+  fn builds_values() {
+    let name = "Alice";
+    let mut address = HashMap::new();
+    address.insert("city".to_owned(), Value::from("Paris"));
+    let mut person = HashMap::new();
+    person.insert("name".to_owned(), Value::from(name));
+    person.insert("tags".to_owned(), Value::List(vec![Value::from("a"), Value::Null]));
+    person.insert("address".to_owned(), Value::Map(address));
+    person.insert("age".to_owned(), Value::Int(42));
+    let person = Value::Map(person);
+
+    assert_eq!(value!({
+      "name": name,
+      "tags": ["a", null],
+      "address": { "city": "Paris" },
+      ("a".to_owned() + "ge"): 42
+    }), person);
+    assert_eq!(value!({
+      "name" => name,
+      "tags" => ["a", null],
+      "address" => { "city" => "Paris" },
+      "age" => 42,
+    }), person);
+  }
+}
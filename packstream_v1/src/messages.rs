@@ -1,8 +1,5 @@
 use std::collections::HashMap;
-use packstream_proc::{bolt_packstream};
-use packstream_core::packer::{PackValue,Packer};
-use packstream_core::unpacker::{UnpackValue,Unpacker};
-use packstream_core::error::BoltError;
+use packstream_proc::{BoltStruct, bolt_enum};
 use crate::value::Value;
 
 /// The `Init` message is a client message used once to initialize the session.
@@ -14,7 +11,8 @@ use crate::value::Value;
 ///
 /// All parameters in the `Init` message are required.
 /// [AckFailure]: crate::messages::AckFailure
-#[bolt_packstream(0x01)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x01)]
 pub struct Init {
   pub client_name: String,
   pub auth_token: HashMap<String, Value>
@@ -22,7 +20,8 @@ pub struct Init {
 
 /// The `Run` message is a client message used to pass a statement for execution
 /// on the server.
-#[bolt_packstream(0x10)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x10)]
 pub struct Run {
   pub statement: String,
   pub parameters: HashMap<String, Value>
@@ -30,17 +29,20 @@ pub struct Run {
 
 /// The `DiscardAll` message is a client message used to discard all remaining
 /// items from the active result stream.
-#[bolt_packstream(0x2F)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x2F)]
 pub struct DiscardAll;
 
 /// The `PullAll` message is a client message used to retrieve all remaining
 /// items from the active result stream.
-#[bolt_packstream(0x3F)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x3F)]
 pub struct PullAll;
 
 /// The `AckFailure` message is a client message used to acknowledge a failure
 /// the server has sent.
-#[bolt_packstream(0x0E)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x0E)]
 pub struct AckFailure;
 
 /// The `Reset` message is a client message used to return the current session
@@ -50,13 +52,15 @@ pub struct AckFailure;
 /// also means clients must be careful about pipelining `Reset`. Only send this
 /// if you are not currently waiting for a result from a prior message, or if
 /// you want to explicitly abort any prior message.
-#[bolt_packstream(0x0F)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x0F)]
 pub struct Reset;
 
 /// The `Record` message is a server detail message used to deliver data from
 /// the server to the client. Each record message contains a single List, which
 /// in turn contains the fields of the record in order.
-#[bolt_packstream(0x71)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x71)]
 pub struct Record {
   pub fields: Vec<Value>
 }
@@ -65,24 +69,28 @@ pub struct Record {
 /// corresponding client message has been received and actioned as intended.
 /// The message contains a map of metadata, the contents of which depend on
 /// the original request
-#[bolt_packstream(0x70)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x70)]
 pub struct Success {
   pub metadata: HashMap<String, Value>
 }
 
 /// The `Failure` message is a server summary message used to signal that a
 /// corresponding client message has encountered an error while being processed.
-#[bolt_packstream(0x7F)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x7F)]
 pub struct Failure {
   pub metadata: HashMap<String, Value>
 }
 
 /// The `Ignored` message is a server summary message used to signal that a
 /// corresponding client message has been ignored and not actioned.
-#[bolt_packstream(0x7E)]
+#[derive(BoltStruct)]
+#[bolt(signature = 0x7E)]
 pub struct Ignored;
 
-
+/// Any message of the protocol, packed and unpacked as the message it holds.
+#[bolt_enum]
 pub enum Message {
   Init(Init),
   Run(Run),
@@ -0,0 +1,491 @@
+//! Comparison of values following Cypher semantics.
+//!
+//! [Value] only implements `PartialEq`, under which `NaN` differs from itself
+//! and `Int(1)` differs from `Float(1.0)`. [cypher_cmp](Value::cypher_cmp)
+//! orders any two values the way `ORDER BY` does, and [OrderedValue] wraps a
+//! value to use that order in sorted or hashed collections:
+//!
+//! ```ignore
+//! let distinct: HashSet<OrderedValue> = rows
+//!   .into_iter()
+//!   .map(OrderedValue::from)
+//!   .collect();
+//! ```
+//!
+//! Values of different types are ordered by type, in ascending order:
+//! maps, nodes, relationships, lists, paths, points, date times, local date
+//! times, dates, times, local times, durations, strings, booleans, numbers,
+//! other structures and finally null. Within a type:
+//! - numbers compare by value, `Int` and `Float` alike, `NaN` being greater
+//!   than any other number and equal to itself,
+//! - lists compare item by item,
+//! - maps compare their entries sorted by key, key first,
+//! - nodes and relationships compare by id, paths by their nodes then their
+//!   relationships,
+//! - points compare by SRID then coordinates, 2D points before 3D ones,
+//! - date times and times compare by instant then offset or zone, legacy
+//!   date times with a zone id by local date time, durations by months, days
+//!   then seconds,
+//! - unknown structures compare by signature then fields, registered ones
+//!   as well, their fields being those written by their dehydrator.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use packstream_core::packer::Packer;
+use packstream_core::unpacker::Unpacker;
+use crate::registry::{self, CustomStruct};
+use crate::structs::{Struct, Path};
+use crate::temporal::Duration;
+use crate::spatial::{Point2D, Point3D};
+use crate::unpacker::unpack_unknown_struct;
+use crate::value::Value;
+
+/// Bounds of the `i64` range as `f64`, the upper one being excluded.
+const I64_MIN_F64: f64 = -9_223_372_036_854_775_808.0;
+const I64_END_F64: f64 = 9_223_372_036_854_775_808.0;
+
+/**
+ * Value compared and hashed with Cypher semantics, see the
+ * [module documentation](self). Two wrapped values are equal when
+ * [cypher_cmp](Value::cypher_cmp) finds them equal.
+ */
+#[derive(Clone, Debug)]
+pub struct OrderedValue(pub Value);
+
+impl OrderedValue {
+  pub fn into_inner(self) -> Value {
+    self.0
+  }
+}
+
+impl From<Value> for OrderedValue {
+  fn from(value: Value) -> Self {
+    OrderedValue(value)
+  }
+}
+
+impl Deref for OrderedValue {
+  type Target = Value;
+
+  fn deref(&self) -> &Value {
+    &self.0
+  }
+}
+
+impl PartialEq for OrderedValue {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for OrderedValue {}
+
+impl PartialOrd for OrderedValue {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for OrderedValue {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.cypher_cmp(&other.0)
+  }
+}
+
+impl Hash for OrderedValue {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    hash_value(&self.0, state)
+  }
+}
+
+impl Value {
+  /// Compares two values the way Cypher `ORDER BY` does.
+  pub fn cypher_cmp(&self, other: &Value) -> Ordering {
+    match (self, other) {
+      (Value::Null, Value::Null) => Ordering::Equal,
+      (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+      (Value::Int(a), Value::Int(b)) => a.cmp(b),
+      (Value::Int(a), Value::Float(b)) => cmp_int_float(*a, *b),
+      (Value::Float(a), Value::Int(b)) => cmp_int_float(*b, *a).reverse(),
+      (Value::Float(a), Value::Float(b)) => cmp_float(*a, *b),
+      (Value::String(a), Value::String(b)) => a.cmp(b),
+      (Value::List(a), Value::List(b)) => cmp_list(a, b),
+      (Value::Map(a), Value::Map(b)) => cmp_map(a, b),
+      (Value::Struct(a), Value::Struct(b)) => cmp_struct(a, b),
+      (a, b) => rank(a).cmp(&rank(b))
+    }
+  }
+}
+
+/// Position of the type of a value in the ascending order.
+fn rank(value: &Value) -> u8 {
+  match value {
+    Value::Map(_) => 0,
+    Value::List(_) => 3,
+    Value::String(_) => 12,
+    Value::Boolean(_) => 13,
+    Value::Int(_) | Value::Float(_) => 14,
+    Value::Struct(s) => struct_rank(s),
+    Value::Null => 16
+  }
+}
+
+fn struct_rank(s: &Struct) -> u8 {
+  match s {
+    Struct::Node(_) => 1,
+    Struct::Relationship(_) | Struct::UnboundRelationship(_) => 2,
+    Struct::Path(_) => 4,
+    Struct::Point2D(_) | Struct::Point3D(_) => 5,
+    Struct::DateTime(_) | Struct::DateTimeZoneId(_)
+    | Struct::LegacyDateTime(_) | Struct::LegacyDateTimeZoneId(_) => 6,
+    Struct::LocalDateTime(_) => 7,
+    Struct::Date(_) => 8,
+    Struct::Time(_) => 9,
+    Struct::LocalTime(_) => 10,
+    Struct::Duration(_) => 11,
+    Struct::Unknown { .. } | Struct::Custom { .. } => 15
+  }
+}
+
+fn cmp_float(a: f64, b: f64) -> Ordering {
+  a.partial_cmp(&b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+/// Compares an integer and a float without losing precision.
+fn cmp_int_float(a: i64, b: f64) -> Ordering {
+  if b.is_nan() || b >= I64_END_F64 {
+    Ordering::Less
+  }
+  else if b < I64_MIN_F64 {
+    Ordering::Greater
+  }
+  else {
+    let whole = b.trunc();
+    a.cmp(&(whole as i64)).then_with(|| cmp_float(0.0, b - whole))
+  }
+}
+
+fn cmp_list(a: &[Value], b: &[Value]) -> Ordering {
+  a.iter()
+    .zip(b)
+    .map(|(x, y)| x.cypher_cmp(y))
+    .find(|o| *o != Ordering::Equal)
+    .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn cmp_map(a: &HashMap<String, Value>, b: &HashMap<String, Value>) -> Ordering {
+  let a = sorted(a);
+  let b = sorted(b);
+  a.iter()
+    .zip(&b)
+    .map(|((ka, va), (kb, vb))| ka.cmp(kb).then_with(|| va.cypher_cmp(vb)))
+    .find(|o| *o != Ordering::Equal)
+    .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn cmp_struct(a: &Struct, b: &Struct) -> Ordering {
+  match (a, b) {
+    (Struct::Node(a), Struct::Node(b)) => a.id.cmp(&b.id),
+    (Struct::Relationship(a), Struct::Relationship(b)) => a.id.cmp(&b.id),
+    (Struct::UnboundRelationship(a), Struct::UnboundRelationship(b)) => a.id.cmp(&b.id),
+    (Struct::Relationship(a), Struct::UnboundRelationship(b)) => a.id.cmp(&b.id),
+    (Struct::UnboundRelationship(a), Struct::Relationship(b)) => a.id.cmp(&b.id),
+    (Struct::Path(a), Struct::Path(b)) => path_ids(a).cmp(&path_ids(b)),
+    (Struct::Unknown { signature: sa, fields: fa },
+     Struct::Unknown { signature: sb, fields: fb }) =>
+      sa.cmp(sb).then_with(|| cmp_list(fa, fb)),
+    (Struct::Custom { signature: sa, value: va },
+     Struct::Custom { signature: sb, value: vb }) =>
+      sa.cmp(sb).then_with(|| cmp_list(&custom_fields(*sa, &**va), &custom_fields(*sb, &**vb))),
+    (a, b) if struct_rank(a) == 5 && struct_rank(b) == 5 => {
+      let (sa, ca) = point_key(a);
+      let (sb, cb) = point_key(b);
+      sa.cmp(&sb).then_with(|| ca
+        .iter()
+        .zip(&cb)
+        .map(|(x, y)| cmp_float(*x, *y))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or_else(|| ca.len().cmp(&cb.len())))
+    },
+    (Struct::Unknown { .. }, Struct::Custom { .. }) => Ordering::Less,
+    (Struct::Custom { .. }, Struct::Unknown { .. }) => Ordering::Greater,
+    (a, b) if struct_rank(a) == struct_rank(b) => temporal_key(a).cmp(&temporal_key(b)),
+    (a, b) => struct_rank(a).cmp(&struct_rank(b))
+  }
+}
+
+/// SRID and coordinates of a point, none for other structures.
+fn point_key(s: &Struct) -> (i64, Vec<f64>) {
+  match s {
+    Struct::Point2D(Point2D { srid, x, y }) => (*srid, vec![*x, *y]),
+    Struct::Point3D(Point3D { srid, x, y, z }) => (*srid, vec![*x, *y, *z]),
+    _ => (0, Vec::new())
+  }
+}
+
+/// Zone of a date time or time, offsets coming first.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Zone<'a> {
+  Offset(i64),
+  Id(&'a str)
+}
+
+/// Months, days, nanoseconds of the instant and zone of a temporal value.
+type TemporalKey<'a> = (i64, i64, i128, Option<Zone<'a>>);
+
+/// Key ordering temporal values of a same rank, `None` for other
+/// structures.
+fn temporal_key(s: &Struct) -> Option<TemporalKey<'_>> {
+  fn instant(n: i128, zone: Option<Zone<'_>>) -> Option<TemporalKey<'_>> {
+    Some((0, 0, n, zone))
+  }
+  let nanos = |seconds: i64, nanoseconds: i64| {
+    i128::from(seconds) * 1_000_000_000 + i128::from(nanoseconds)
+  };
+  match s {
+    Struct::Date(x) => instant(x.days.into(), None),
+    Struct::LocalTime(x) => instant(x.nanoseconds.into(), None),
+    Struct::Time(x) =>
+      instant(nanos(-x.tz_offset_seconds, x.nanoseconds), Some(Zone::Offset(x.tz_offset_seconds))),
+    Struct::LocalDateTime(x) => instant(nanos(x.seconds, x.nanoseconds), None),
+    Struct::DateTime(x) =>
+      instant(nanos(x.seconds, x.nanoseconds), Some(Zone::Offset(x.tz_offset_seconds))),
+    Struct::LegacyDateTime(x) =>
+      instant(nanos(x.seconds, x.nanoseconds) - nanos(x.tz_offset_seconds, 0),
+              Some(Zone::Offset(x.tz_offset_seconds))),
+    Struct::DateTimeZoneId(x) => instant(nanos(x.seconds, x.nanoseconds), Some(Zone::Id(&x.tz_id))),
+    Struct::LegacyDateTimeZoneId(x) =>
+      instant(nanos(x.seconds, x.nanoseconds), Some(Zone::Id(&x.tz_id))),
+    Struct::Duration(Duration { months, days, seconds, nanoseconds }) =>
+      Some((*months, *days, nanos(*seconds, *nanoseconds), None)),
+    _ => None
+  }
+}
+
+/**
+ * Fields of a registered structure, as written by its dehydrator and read
+ * back as plain values. None when the signature is no longer registered or
+ * the structure cannot be packed, such structures comparing equal.
+ */
+fn custom_fields(signature: u8, value: &dyn CustomStruct) -> Vec<Value> {
+  let mut packer = Packer::new();
+  let packed = registry::dehydrator(signature)
+    .map_or(false, |dehydrate| dehydrate(&mut packer, value.clone_box()).is_ok());
+  if !packed {
+    return Vec::new();
+  }
+  match unpack_unknown_struct(&mut Unpacker::new(packer[..].to_vec())) {
+    Ok(Struct::Unknown { fields, .. }) => fields,
+    _ => Vec::new()
+  }
+}
+
+fn path_ids(p: &Path) -> (Vec<i64>, Vec<i64>) {
+  (p.nodes.iter().map(|n| n.id).collect(),
+   p.relationships.iter().map(|r| r.id).collect())
+}
+
+fn sorted(map: &HashMap<String, Value>) -> Vec<(&String, &Value)> {
+  let mut entries = map.iter().collect::<Vec<_>>();
+  entries.sort_by(|a, b| a.0.cmp(b.0));
+  entries
+}
+
+/// Hashes a value consistently with [cypher_cmp](Value::cypher_cmp): equal
+/// numbers hash the same whether integers or floats.
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+  rank(value).hash(state);
+  match value {
+    Value::Null => (),
+    Value::Boolean(b) => b.hash(state),
+    Value::Int(n) => n.hash(state),
+    Value::Float(n) => hash_float(*n, state),
+    Value::String(cs) => cs.hash(state),
+    Value::List(xs) => {
+      xs.len().hash(state);
+      xs.iter().for_each(|x| hash_value(x, state));
+    },
+    Value::Map(ps) => {
+      ps.len().hash(state);
+      sorted(ps).into_iter().for_each(|(k, v)| {
+        k.hash(state);
+        hash_value(v, state);
+      });
+    },
+    Value::Struct(s) => match s {
+      Struct::Node(x) => x.id.hash(state),
+      Struct::Relationship(x) => x.id.hash(state),
+      Struct::UnboundRelationship(x) => x.id.hash(state),
+      Struct::Path(x) => path_ids(x).hash(state),
+      Struct::Unknown { signature, fields } => {
+        signature.hash(state);
+        fields.len().hash(state);
+        fields.iter().for_each(|x| hash_value(x, state));
+      },
+      Struct::Custom { signature, value } => {
+        let fields = custom_fields(*signature, &**value);
+        signature.hash(state);
+        fields.len().hash(state);
+        fields.iter().for_each(|x| hash_value(x, state));
+      },
+      s @ Struct::Point2D(_) | s @ Struct::Point3D(_) => {
+        let (srid, coordinates) = point_key(s);
+        srid.hash(state);
+        coordinates.len().hash(state);
+        coordinates.into_iter().for_each(|n| hash_float(n, state));
+      },
+      s => temporal_key(s).hash(state)
+    }
+  }
+}
+
+fn hash_float<H: Hasher>(n: f64, state: &mut H) {
+  if n.is_nan() {
+    f64::NAN.to_bits().hash(state)
+  }
+  else if n.fract() == 0.0 && (I64_MIN_F64..I64_END_F64).contains(&n) {
+    (n as i64).hash(state)
+  }
+  else {
+    n.to_bits().hash(state)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::hash_map::DefaultHasher;
+  use std::collections::HashSet;
+  use crate::temporal::{Date, DateTime, LegacyDateTime};
+  use super::*;
+
+  fn hash(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    OrderedValue(value.clone()).hash(&mut hasher);
+    hasher.finish()
+  }
+
+  fn map(entries: &[(&str, Value)]) -> Value {
+    Value::Map(entries.iter().map(|(k, v)| ((*k).to_owned(), v.clone())).collect())
+  }
+
+  fn samples() -> Vec<Value> {
+    vec![
+      Value::Null,
+      Value::Boolean(false),
+      Value::Boolean(true),
+      Value::Int(-1),
+      Value::Int(1),
+      Value::Int(i64::MAX),
+      Value::Float(-0.0),
+      Value::Float(0.0),
+      Value::Float(1.0),
+      Value::Float(1.5),
+      Value::Float(9_223_372_036_854_775_808.0),
+      Value::Float(f64::INFINITY),
+      Value::Float(f64::NAN),
+      Value::String("a".to_owned()),
+      Value::List(vec![Value::Int(1)]),
+      Value::List(vec![Value::Float(1.0), Value::Null]),
+      map(&[("a", Value::Int(1)), ("b", Value::Null)]),
+      map(&[("b", Value::Null), ("a", Value::Float(1.0))]),
+      Value::Struct(Struct::Date(Date { days: 1 })),
+      Value::Struct(Struct::DateTime(DateTime { seconds: 0, nanoseconds: 0, tz_offset_seconds: 3600 })),
+      Value::Struct(Struct::LegacyDateTime(LegacyDateTime {
+        seconds: 3600,
+        nanoseconds: 0,
+        tz_offset_seconds: 3600
+      })),
+      Value::Struct(Struct::Point2D(Point2D { srid: 7203, x: 1.0, y: f64::NAN })),
+      Value::Struct(Struct::Unknown { signature: 0x20, fields: vec![Value::Int(1)] })
+    ]
+  }
+
+  #[test]
+  fn orders_types_then_values() {
+    let mut values = [
+      Value::Null,
+      Value::Float(f64::NAN),
+      Value::Int(2),
+      Value::Float(1.5),
+      Value::Boolean(true),
+      Value::String("a".to_owned()),
+      Value::List(vec![]),
+      map(&[])
+    ];
+    values.sort_by(Value::cypher_cmp);
+    assert_eq!(values[..5], [
+      map(&[]),
+      Value::List(vec![]),
+      Value::String("a".to_owned()),
+      Value::Boolean(true),
+      Value::Float(1.5)
+    ]);
+    assert_eq!(values[5], Value::Int(2));
+    assert!(matches!(values[6], Value::Float(n) if n.is_nan()));
+    assert_eq!(values[7], Value::Null);
+  }
+
+  #[test]
+  fn compares_integers_and_floats_by_value() {
+    assert_eq!(Value::Int(1).cypher_cmp(&Value::Float(1.0)), Ordering::Equal);
+    assert_eq!(Value::Int(1).cypher_cmp(&Value::Float(1.5)), Ordering::Less);
+    assert_eq!(Value::Int(-1).cypher_cmp(&Value::Float(-1.5)), Ordering::Greater);
+    assert_eq!(Value::Int(i64::MAX).cypher_cmp(&Value::Float(9_223_372_036_854_775_808.0)),
+               Ordering::Less);
+    assert_eq!(Value::Float(f64::NAN).cypher_cmp(&Value::Float(f64::NAN)), Ordering::Equal);
+    assert_eq!(Value::Float(f64::INFINITY).cypher_cmp(&Value::Float(f64::NAN)), Ordering::Less);
+  }
+
+  #[test]
+  fn is_a_total_order() {
+    let values = samples();
+    for a in &values {
+      assert_eq!(a.cypher_cmp(a), Ordering::Equal, "{:?}", a);
+      for b in &values {
+        assert_eq!(a.cypher_cmp(b), b.cypher_cmp(a).reverse(), "{:?} {:?}", a, b);
+        for c in &values {
+          if a.cypher_cmp(b) != Ordering::Greater && b.cypher_cmp(c) != Ordering::Greater {
+            assert_ne!(a.cypher_cmp(c), Ordering::Greater, "{:?} {:?} {:?}", a, b, c);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn hashes_equal_values_the_same() {
+    let values = samples();
+    for a in &values {
+      for b in values.iter().filter(|b| a.cypher_cmp(b) == Ordering::Equal) {
+        assert_eq!(hash(a), hash(b), "{:?} {:?}", a, b);
+        assert_eq!(OrderedValue(a.clone()), OrderedValue(b.clone()));
+      }
+    }
+    let distinct = values.into_iter().map(OrderedValue::from).collect::<HashSet<_>>();
+    // -0.0 and 0.0, 1 and 1.0, the two maps and the two date times collapse.
+    assert_eq!(distinct.len(), 19);
+  }
+
+  #[derive(Clone, Debug, PartialEq, packstream_proc::BoltStruct)]
+  #[bolt(signature = 0x43)]
+  struct Tags {
+    tags: HashMap<String, Value>
+  }
+
+  #[test]
+  fn compares_registered_structures_by_fields() {
+    registry::register::<Tags>().unwrap();
+    let tags = |n: i64| {
+      let tags = (0..n).map(|i| (i.to_string(), Value::Int(i))).collect::<HashMap<_, _>>();
+      Value::Struct(Struct::Custom { signature: 0x43, value: Box::new(Tags { tags }) })
+    };
+    // Each map has its own hasher keys, hence its own iteration order.
+    let (a, b) = (tags(32), tags(32));
+    assert_eq!(a.cypher_cmp(&b), Ordering::Equal);
+    assert_eq!(hash(&a), hash(&b));
+    assert_eq!(tags(1).cypher_cmp(&tags(2)), Ordering::Less);
+    assert_ne!(hash(&tags(1)), hash(&tags(2)));
+    assert!(registry::unregister(0x43));
+  }
+}
@@ -1,50 +1,66 @@
 use packstream_core::packer::{Packer,PackValue};
 use packstream_core::error::BoltError;
 use crate::value::*;
-use crate::structs::{Struct};
-use crate::messages::Message;
 
 impl PackValue<Value> for Packer {
   fn pack(&mut self, val: Value) -> Result<(), BoltError> {
     match val {
-      // TODO found a way to impl <T> for Option<T>
-      // Value::Null       => self.pack(None),
+      Value::Null       => self.pack_null(),
       Value::Boolean(b) => self.pack(b),
       Value::Int(n)     => self.pack(n),
       Value::Float(n)   => self.pack(n),
       Value::String(cs) => self.pack(cs),
       Value::List(xs)   => self.pack(xs),
       Value::Map(ps)    => self.pack(ps),
-      Value::Struct(x)  => self.pack(x),
-      _                 => Err(BoltError::Unmanaged)
+      Value::Struct(x)  => self.pack(x)
     }
   }
 }
 
-impl PackValue<Struct> for Packer {
-  fn pack(&mut self, val: Struct) -> Result<(), BoltError> {
-    match val {
-      Struct::Node(x)                => self.pack(x),
-      Struct::Relationship(x)        => self.pack(x),
-      Struct::Path(x)                => self.pack(x),
-      Struct::UnboundRelationship(x) => self.pack(x),
-    }
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use packstream_core::consts::NULL;
+  use packstream_core::unpacker::{Unpacker, UnpackValue};
+  use crate::structs::{Struct, Node};
+  use super::*;
+
+  fn round_trip(value: Value) -> (Vec<u8>, Value) {
+    let mut packer = Packer::new();
+    packer.pack(value).unwrap();
+    let bytes = packer[..].to_vec();
+    let value = Unpacker::new(bytes.clone()).unpack().unwrap();
+    (bytes, value)
   }
-}
 
-impl PackValue<Message> for Packer {
-  fn pack(&mut self, val: Message) -> Result<(), BoltError> {
-    match val {
-      Message::Init(x)       => self.pack(x),
-      Message::Run(x)        => self.pack(x),
-      Message::DiscardAll(x) => self.pack(x),
-      Message::PullAll(x)    => self.pack(x),
-      Message::AckFailure(x) => self.pack(x),
-      Message::Reset(x)      => self.pack(x),
-      Message::Record(x)     => self.pack(x),
-      Message::Success(x)    => self.pack(x),
-      Message::Failure(x)    => self.pack(x),
-      Message::Ignored(x)    => self.pack(x),
-    }
+  #[test]
+  fn round_trips_null() {
+    assert_eq!(round_trip(Value::Null), (vec![NULL], Value::Null));
+  }
+
+  #[test]
+  fn round_trips_nested_nulls() {
+    let mut properties = HashMap::new();
+    properties.insert("nickname".to_owned(), Value::Null);
+    let node = Node { id: 1, labels: vec![], properties, element_id: None };
+    let mut map = HashMap::new();
+    map.insert("none".to_owned(), Value::Null);
+    map.insert("list".to_owned(), Value::List(vec![Value::Null, Value::Int(1), Value::Null]));
+    map.insert("node".to_owned(), Value::Struct(Struct::Node(node)));
+    map.insert("unknown".to_owned(), Value::Struct(Struct::Unknown {
+      signature: 0x20,
+      fields: vec![Value::Null]
+    }));
+    let value = Value::Map(map);
+    assert_eq!(round_trip(value.clone()).1, value);
+  }
+
+  #[test]
+  fn round_trips_optional_values() {
+    let mut packer = Packer::new();
+    packer.pack(vec![Some(Value::Int(1)), None]).unwrap();
+    let mut unpacker = Unpacker::new(packer[..].to_vec());
+    let values: Vec<Option<Value>> = unpacker.unpack().unwrap();
+    assert_eq!(values, vec![Some(Value::Int(1)), None]);
   }
 }
@@ -0,0 +1,312 @@
+//! Traversal of paths.
+//!
+//! A [Path] holds its distinct nodes and relationships along with a sequence
+//! of indexes alternating between them. Each step is the index of a
+//! relationship, counting from 1 and negative when the relationship is
+//! traversed from its end node to its start node, followed by the index of
+//! the node it leads to. The path starts at the first node.
+//!
+//! [segments](Path::segments) decodes the sequence into the steps of the
+//! path, relationships being bound to the nodes they connect, and
+//! [from_segments](Path::from_segments) builds a path from its steps:
+//!
+//! ```ignore
+//! for Segment { start, relationship, end } in path.segments()? {
+//!   println!("({})-[:{}]->({})", start.id, relationship.type_name, end.id);
+//! }
+//! let path = Path::from_segments(vec![(alice, knows, bob), (bob, knows, carol)])?;
+//! ```
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::iter::FusedIterator;
+use crate::structs::{Node, Path, Relationship, UnboundRelationship};
+
+/// Step of a path, its relationship going from `start` to `end` or the other
+/// way round.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment<'a> {
+  pub start: &'a Node,
+  pub relationship: Relationship,
+  pub end: &'a Node
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathError {
+  /// The path has no nodes, not even a start.
+  NoNodes,
+  /// The sequence has an odd number of indexes.
+  OddSequence(usize),
+  /// The node index at `position` in the sequence points out of the nodes.
+  NodeOutOfRange { position: usize, index: i64 },
+  /// The relationship index at `position` in the sequence is zero or points
+  /// out of the relationships.
+  RelationshipOutOfRange { position: usize, index: i64 },
+  /// A segment does not start where the previous one ends.
+  Disconnected { segment: usize },
+  /// The relationship of a segment does not connect its nodes.
+  RelationshipMismatch { segment: usize }
+}
+
+impl fmt::Display for PathError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PathError::NoNodes => f.write_str("path has no nodes"),
+      PathError::OddSequence(len) => write!(f, "path sequence has an odd length of {}", len),
+      PathError::NodeOutOfRange { position, index } =>
+        write!(f, "node index {} at {} is out of the path", index, position),
+      PathError::RelationshipOutOfRange { position, index } =>
+        write!(f, "relationship index {} at {} is out of the path", index, position),
+      PathError::Disconnected { segment } =>
+        write!(f, "segment {} does not start at the end of the previous one", segment),
+      PathError::RelationshipMismatch { segment } =>
+        write!(f, "relationship of segment {} does not connect its nodes", segment)
+    }
+  }
+}
+
+impl Error for PathError {}
+
+impl Path {
+  /// Checks that the sequence has steps of a relationship and a node, both
+  /// pointing in the path.
+  pub fn validate(&self) -> Result<(), PathError> {
+    if self.nodes.is_empty() {
+      return Err(PathError::NoNodes);
+    }
+    if self.sequence.len() % 2 != 0 {
+      return Err(PathError::OddSequence(self.sequence.len()));
+    }
+    self.sequence.iter().enumerate().try_for_each(|(position, &index)| {
+      if position % 2 == 0 {
+        self.relationship_at(index)
+          .map(|_| ())
+          .ok_or(PathError::RelationshipOutOfRange { position, index })
+      }
+      else {
+        self.node_at(index)
+          .map(|_| ())
+          .ok_or(PathError::NodeOutOfRange { position, index })
+      }
+    })
+  }
+
+  pub fn start(&self) -> Option<&Node> {
+    self.nodes.first()
+  }
+
+  /// Last node of the path, the start when it has no relationships.
+  pub fn end(&self) -> Option<&Node> {
+    match self.sequence.last() {
+      Some(&index) if self.sequence.len() % 2 == 0 => self.node_at(index),
+      Some(_) => None,
+      None => self.start()
+    }
+  }
+
+  /// Number of relationships traversed, as `length(path)` in Cypher.
+  pub fn len(&self) -> usize {
+    self.sequence.len() / 2
+  }
+
+  /// Whether the path is a single node.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Steps of the path in order, once the path is [validated](Path::validate).
+  pub fn segments(&self) -> Result<Segments<'_>, PathError> {
+    self.validate()?;
+    Ok(Segments { path: self, position: 0 })
+  }
+
+  /**
+   * Builds a path from its steps, each of a start node, a relationship and
+   * an end node. Nodes and relationships are stored once however many times
+   * the path traverses them. Fails when there are no segments, when a
+   * segment doesn't start where the previous one ends, or when its
+   * relationship doesn't connect its nodes.
+   */
+  pub fn from_segments<I>(segments: I) -> Result<Path, PathError>
+    where I: IntoIterator<Item = (Node, Relationship, Node)> {
+    let mut path = Path { nodes: Vec::new(), relationships: Vec::new(), sequence: Vec::new() };
+    for (segment, (start, relationship, end)) in segments.into_iter().enumerate() {
+      let start_id = start.id;
+      match path.end() {
+        None => path.nodes.push(start),
+        Some(last) if last.id == start_id => (),
+        Some(_) => return Err(PathError::Disconnected { segment })
+      }
+      let forward = relationship.start_node_id == start_id && relationship.end_node_id == end.id;
+      let backward = relationship.start_node_id == end.id && relationship.end_node_id == start_id;
+      if !forward && !backward {
+        return Err(PathError::RelationshipMismatch { segment });
+      }
+      let r = index_of(&mut path.relationships, relationship.id, |r| r.id, || UnboundRelationship {
+        id: relationship.id,
+        type_name: relationship.type_name,
+        properties: relationship.properties,
+        element_id: relationship.element_id
+      }) + 1;
+      let n = index_of(&mut path.nodes, end.id, |n| n.id, || end);
+      path.sequence.push(if forward { r } else { -r });
+      path.sequence.push(n);
+    }
+    if path.nodes.is_empty() { Err(PathError::NoNodes) } else { Ok(path) }
+  }
+
+  fn node_at(&self, index: i64) -> Option<&Node> {
+    usize::try_from(index).ok().and_then(|i| self.nodes.get(i))
+  }
+
+  fn relationship_at(&self, index: i64) -> Option<&UnboundRelationship> {
+    usize::try_from(index.unsigned_abs()).ok()
+      .and_then(|i| i.checked_sub(1))
+      .and_then(|i| self.relationships.get(i))
+  }
+}
+
+/// Index of the entity with `id` in `entities`, pushing it when missing.
+fn index_of<T>(entities: &mut Vec<T>, id: i64, id_of: impl Fn(&T) -> i64,
+               entity: impl FnOnce() -> T) -> i64 {
+  let index = match entities.iter().position(|x| id_of(x) == id) {
+    Some(index) => index,
+    None => {
+      entities.push(entity());
+      entities.len() - 1
+    }
+  };
+  i64::try_from(index).unwrap_or(i64::MAX)
+}
+
+/// Iterator over the [segments](Path::segments) of a path.
+#[derive(Clone, Debug)]
+pub struct Segments<'a> {
+  path: &'a Path,
+  position: usize
+}
+
+impl<'a> Iterator for Segments<'a> {
+  type Item = Segment<'a>;
+
+  fn next(&mut self) -> Option<Segment<'a>> {
+    let path = self.path;
+    let (r, n) = match path.sequence.get(self.position..self.position + 2)? {
+      [r, n] => (*r, *n),
+      _ => return None
+    };
+    let start = match self.position {
+      0 => path.start()?,
+      p => path.node_at(path.sequence[p - 1])?
+    };
+    let end = path.node_at(n)?;
+    let unbound = path.relationship_at(r)?;
+    let relationship = if r < 0 {
+      unbound.clone().bind(end, start)
+    }
+    else {
+      unbound.clone().bind(start, end)
+    };
+    self.position += 2;
+    Some(Segment { start, relationship, end })
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.path.sequence.len().saturating_sub(self.position) / 2;
+    (len, Some(len))
+  }
+}
+
+impl ExactSizeIterator for Segments<'_> {}
+
+impl FusedIterator for Segments<'_> {}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use super::*;
+
+  fn node(id: i64) -> Node {
+    Node { id, labels: vec!["Person".to_owned()], properties: HashMap::new(), element_id: None }
+  }
+
+  fn knows(id: i64, start: i64, end: i64) -> Relationship {
+    Relationship {
+      id,
+      start_node_id: start,
+      end_node_id: end,
+      type_name: "KNOWS".to_owned(),
+      properties: HashMap::new(),
+      element_id: None,
+      start_node_element_id: None,
+      end_node_element_id: None
+    }
+  }
+
+  #[test]
+  fn builds_and_traverses_paths() {
+    // (1)-[10]->(2)<-[11]-(3)-[12]->(1)
+    let path = Path::from_segments(vec![
+      (node(1), knows(10, 1, 2), node(2)),
+      (node(2), knows(11, 3, 2), node(3)),
+      (node(3), knows(12, 3, 1), node(1))
+    ]).unwrap();
+    assert_eq!(path.nodes.iter().map(|n| n.id).collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(path.sequence, [1, 1, -2, 2, 3, 0]);
+    assert_eq!(path.validate(), Ok(()));
+    assert_eq!(path.len(), 3);
+    assert_eq!(path.start().map(|n| n.id), Some(1));
+    assert_eq!(path.end().map(|n| n.id), Some(1));
+
+    let segments = path.segments().unwrap();
+    assert_eq!(segments.len(), 3);
+    let steps = segments
+      .map(|s| (s.start.id, s.relationship.start_node_id, s.relationship.end_node_id, s.end.id))
+      .collect::<Vec<_>>();
+    assert_eq!(steps, [(1, 1, 2, 2), (2, 3, 2, 3), (3, 3, 1, 1)]);
+  }
+
+  #[test]
+  fn single_node_paths_are_empty() {
+    let path = Path { nodes: vec![node(1)], relationships: vec![], sequence: vec![] };
+    assert_eq!(path.validate(), Ok(()));
+    assert!(path.is_empty());
+    assert_eq!(path.end().map(|n| n.id), Some(1));
+    assert_eq!(path.segments().unwrap().count(), 0);
+  }
+
+  #[test]
+  fn rejects_invalid_sequences() {
+    let unbound = UnboundRelationship {
+      id: 10,
+      type_name: "KNOWS".to_owned(),
+      properties: HashMap::new(),
+      element_id: None
+    };
+    let path = |sequence: Vec<i64>| Path {
+      nodes: vec![node(1), node(2)],
+      relationships: vec![unbound.clone()],
+      sequence
+    };
+    assert_eq!(Path { nodes: vec![], relationships: vec![], sequence: vec![] }.validate(),
+               Err(PathError::NoNodes));
+    assert_eq!(path(vec![1]).validate(), Err(PathError::OddSequence(1)));
+    assert_eq!(path(vec![1]).end(), None);
+    assert_eq!(path(vec![0, 1]).validate(), Err(PathError::RelationshipOutOfRange { position: 0, index: 0 }));
+    assert_eq!(path(vec![1, 1, -2, 0]).validate(),
+               Err(PathError::RelationshipOutOfRange { position: 2, index: -2 }));
+    assert_eq!(path(vec![1, 2]).validate(), Err(PathError::NodeOutOfRange { position: 1, index: 2 }));
+    assert_eq!(path(vec![1, -1]).segments().err(), Some(PathError::NodeOutOfRange { position: 1, index: -1 }));
+  }
+
+  #[test]
+  fn rejects_invalid_segments() {
+    assert_eq!(Path::from_segments(vec![]), Err(PathError::NoNodes));
+    assert_eq!(Path::from_segments(vec![
+      (node(1), knows(10, 1, 2), node(2)),
+      (node(3), knows(11, 3, 4), node(4))
+    ]), Err(PathError::Disconnected { segment: 1 }));
+    assert_eq!(Path::from_segments(vec![(node(1), knows(10, 1, 3), node(2))]),
+               Err(PathError::RelationshipMismatch { segment: 0 }));
+  }
+}
@@ -0,0 +1,228 @@
+//! Runtime registry of user-defined structures.
+//!
+//! Applications extending the protocol register their own structure types
+//! along with their signature. Unpacking a [Value](crate::value::Value) then
+//! produces them as [Struct::Custom](crate::structs::Struct::Custom) instead
+//! of [Struct::Unknown](crate::structs::Struct::Unknown), and packing such a
+//! value writes them back through the registered dehydrator.
+//!
+//! ```ignore
+//! #[bolt_packstream(0x41)]
+//! #[derive(Clone, Debug, PartialEq)]
+//! pub struct Shard {
+//!   pub id: i64,
+//!   pub name: String
+//! }
+//!
+//! registry::register::<Shard>()?;
+//! ```
+//!
+//! Signatures of the structures known by this crate are always unpacked as
+//! their built-in type, and registering them fails.
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::RwLock;
+use packstream_core::consts::*;
+use packstream_core::packer::{PackValue,Packer};
+use packstream_core::unpacker::{UnpackValue,Unpacker};
+use packstream_core::structure::BoltStructure;
+use packstream_core::error::BoltError;
+
+/// Reads a registered structure, header and signature included.
+pub type Hydrator = fn(&mut Unpacker) -> Result<Box<dyn CustomStruct>, BoltError>;
+
+/// Writes a registered structure, header and signature included.
+pub type Dehydrator = fn(&mut Packer, Box<dyn CustomStruct>) -> Result<(), BoltError>;
+
+/// Structure value held by [Struct::Custom](crate::structs::Struct::Custom).
+/// Implemented for every `Clone + Debug + PartialEq` type.
+pub trait CustomStruct: Any + Debug + Send + Sync {
+  fn as_any(&self) -> &dyn Any;
+  fn into_any(self: Box<Self>) -> Box<dyn Any>;
+  fn eq_struct(&self, other: &dyn CustomStruct) -> bool;
+  fn clone_box(&self) -> Box<dyn CustomStruct>;
+}
+
+impl<T> CustomStruct for T
+  where T: Any + Clone + Debug + PartialEq + Send + Sync {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn into_any(self: Box<Self>) -> Box<dyn Any> {
+    self
+  }
+
+  fn eq_struct(&self, other: &dyn CustomStruct) -> bool {
+    other.as_any().downcast_ref::<T>() == Some(self)
+  }
+
+  fn clone_box(&self) -> Box<dyn CustomStruct> {
+    Box::new(self.clone())
+  }
+}
+
+impl dyn CustomStruct {
+  pub fn is<T: Any>(&self) -> bool {
+    self.as_any().is::<T>()
+  }
+
+  pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+    self.as_any().downcast_ref::<T>()
+  }
+
+  pub fn downcast<T: Any>(self: Box<Self>) -> Result<Box<T>, Box<dyn Any>> {
+    self.into_any().downcast::<T>()
+  }
+}
+
+impl PartialEq for dyn CustomStruct {
+  fn eq(&self, other: &Self) -> bool {
+    self.eq_struct(other)
+  }
+}
+
+impl Clone for Box<dyn CustomStruct> {
+  fn clone(&self) -> Self {
+    (**self).clone_box()
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+  hydrate: Hydrator,
+  dehydrate: Dehydrator
+}
+
+static REGISTRY: RwLock<BTreeMap<u8, Entry>> = RwLock::new(BTreeMap::new());
+
+/**
+ * Registers `T` as the structure type of its own signature, replacing any
+ * previous registration. Fails with
+ * [RegisterBuiltInStructSignature](BoltError::RegisterBuiltInStructSignature)
+ * when the signature is the one of a structure known by this crate.
+ */
+pub fn register<T>() -> Result<(), BoltError>
+  where T: CustomStruct + BoltStructure,
+        Packer: PackValue<T>,
+        Unpacker: UnpackValue<T> {
+  register_with(T::SIGNATURE, hydrate::<T>, dehydrate::<T>)
+}
+
+/// Registers custom conversion functions for `signature`, replacing any
+/// previous registration. Fails as [register] does.
+pub fn register_with(signature: u8, hydrate: Hydrator, dehydrate: Dehydrator)
+                     -> Result<(), BoltError> {
+  if is_built_in(signature) {
+    return Err(BoltError::RegisterBuiltInStructSignature { signature });
+  }
+  REGISTRY
+    .write()
+    .unwrap_or_else(|e| e.into_inner())
+    .insert(signature, Entry { hydrate, dehydrate });
+  Ok(())
+}
+
+/// Removes the registration of `signature`. Returns whether it was
+/// registered.
+pub fn unregister(signature: u8) -> bool {
+  REGISTRY
+    .write()
+    .unwrap_or_else(|e| e.into_inner())
+    .remove(&signature)
+    .is_some()
+}
+
+pub fn is_registered(signature: u8) -> bool {
+  entry(signature).is_some()
+}
+
+pub(crate) fn hydrator(signature: u8) -> Option<Hydrator> {
+  entry(signature).map(|e| e.hydrate)
+}
+
+pub(crate) fn dehydrator(signature: u8) -> Option<Dehydrator> {
+  entry(signature).map(|e| e.dehydrate)
+}
+
+/// Whether `signature` is the one of a structure of [Struct](crate::structs::Struct).
+fn is_built_in(signature: u8) -> bool {
+  matches!(signature,
+    STRUCT_NODE | STRUCT_RELATIONSHIP | STRUCT_PATH | STRUCT_UNBOUND_RELATIONSHIP
+    | STRUCT_DATE | STRUCT_TIME | STRUCT_LOCAL_TIME | STRUCT_DATE_TIME
+    | STRUCT_DATE_TIME_ZONE_ID | STRUCT_LEGACY_DATE_TIME | STRUCT_LEGACY_DATE_TIME_ZONE_ID
+    | STRUCT_LOCAL_DATE_TIME | STRUCT_DURATION | STRUCT_POINT_2D | STRUCT_POINT_3D)
+}
+
+fn entry(signature: u8) -> Option<Entry> {
+  REGISTRY
+    .read()
+    .unwrap_or_else(|e| e.into_inner())
+    .get(&signature)
+    .copied()
+}
+
+fn hydrate<T>(unpacker: &mut Unpacker) -> Result<Box<dyn CustomStruct>, BoltError>
+  where T: CustomStruct,
+        Unpacker: UnpackValue<T> {
+  unpacker.unpack().map(|x: T| Box::new(x) as Box<dyn CustomStruct>)
+}
+
+fn dehydrate<T>(packer: &mut Packer, value: Box<dyn CustomStruct>) -> Result<(), BoltError>
+  where T: CustomStruct,
+        Packer: PackValue<T> {
+  value
+    .downcast::<T>()
+    .map_err(|_| BoltError::PackCustomStructTypeMismatch)
+    .and_then(|x| packer.pack(*x))
+}
+
+#[cfg(test)]
+mod tests {
+  use packstream_proc::BoltStruct;
+  use crate::structs::Struct;
+  use crate::value::Value;
+  use super::*;
+
+  #[derive(Clone, Debug, PartialEq, BoltStruct)]
+  #[bolt(signature = 0x41)]
+  struct Shard {
+    id: i64,
+    name: String
+  }
+
+  #[derive(Clone, Debug, PartialEq, BoltStruct)]
+  #[bolt(signature = 0x4E)]
+  struct Vertex {
+    id: i64
+  }
+
+  #[test]
+  fn rejects_built_in_signatures() {
+    assert!(matches!(
+      register::<Vertex>(),
+      Err(BoltError::RegisterBuiltInStructSignature { signature: 0x4E })
+    ));
+    assert!(matches!(
+      register_with(0x49, hydrate::<Shard>, dehydrate::<Shard>),
+      Err(BoltError::RegisterBuiltInStructSignature { signature: 0x49 })
+    ));
+    assert!(!is_registered(0x4E));
+    assert!(!is_registered(0x49));
+  }
+
+  #[test]
+  fn round_trips_registered_structures() {
+    register::<Shard>().unwrap();
+    let shard = Shard { id: 1, name: "eu".to_owned() };
+    let value = Value::Struct(Struct::Custom { signature: 0x41, value: Box::new(shard.clone()) });
+    let mut packer = Packer::new();
+    packer.pack(value.clone()).unwrap();
+    assert_eq!(packer[1], 0x41);
+    let mut unpacker = Unpacker::new(packer[..].to_vec());
+    let unpacked: Value = unpacker.unpack().unwrap();
+    assert_eq!(unpacked, value);
+    assert!(unregister(0x41));
+  }
+}
@@ -0,0 +1,224 @@
+//! Records paired with the names of their columns.
+//!
+//! The fields of a [Record] come in the order of the columns listed by the
+//! `fields` metadata of the [Success] answering [Run](crate::messages::Run).
+//! A [Row] pairs them back, so that columns can be read by name:
+//!
+//! ```ignore
+//! let keys = success.fields()?;
+//! let row = Row::from_record(keys.clone(), record);
+//! let count: i64 = row.get("count")?;
+//!
+//! let (name, age) = Row::from_record(keys.clone(), next_record).into_typed::<(String, i64)>()?;
+//! ```
+//!
+//! Without the keys, [Record::get] reads a field by its column index.
+//!
+//! Structs are read from a row by column name with `#[derive(FromRow)]`.
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::convert::{ConversionError, FromValue};
+use crate::messages::{Record, Success};
+use crate::value::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+  keys: Arc<[String]>,
+  fields: Vec<Value>
+}
+
+impl Row {
+  /// Creates a row of `fields`, named by `keys` in the same order. Keys are
+  /// usually shared by every row of a result.
+  pub fn new(keys: Arc<[String]>, fields: Vec<Value>) -> Self {
+    Row { keys, fields }
+  }
+
+  pub fn from_record(keys: Arc<[String]>, record: Record) -> Self {
+    Row::new(keys, record.fields)
+  }
+
+  pub fn keys(&self) -> &[String] {
+    &self.keys
+  }
+
+  pub fn fields(&self) -> &[Value] {
+    &self.fields
+  }
+
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Gets the value of the column `key`.
+  pub fn value(&self, key: &str) -> Option<&Value> {
+    self.position(key).and_then(|i| self.fields.get(i))
+  }
+
+  /// Gets the value of the column `key`, converted to `T`.
+  pub fn get<T: FromValue>(&self, key: &str) -> Result<T, ConversionError> {
+    let value = self.value(key)
+      .cloned()
+      .ok_or_else(|| ConversionError::MissingKey(key.to_owned()))?;
+    T::from_value(value).map_err(|e| e.in_key(key))
+  }
+
+  pub fn into_fields(self) -> Vec<Value> {
+    self.fields
+  }
+
+  /// Converts the row to `T`, as [FromRow::from_row].
+  pub fn into_typed<T: FromRow>(self) -> Result<T, ConversionError> {
+    T::from_row(self)
+  }
+
+  /// Converts the row to a map of its values by column name.
+  pub fn into_map(self) -> HashMap<String, Value> {
+    self.keys
+      .iter()
+      .cloned()
+      .zip(self.fields)
+      .collect()
+  }
+
+  fn position(&self, key: &str) -> Option<usize> {
+    self.keys.iter().position(|k| k == key)
+  }
+}
+
+/// Conversion of a [Row] to a Rust type, see
+/// [derive(FromRow)](packstream_proc::FromRow). Tuples are converted from
+/// the columns in order.
+pub trait FromRow: Sized {
+  fn from_row(row: Row) -> Result<Self, ConversionError>;
+}
+
+impl FromRow for Row {
+  fn from_row(row: Row) -> Result<Self, ConversionError> {
+    Ok(row)
+  }
+}
+
+impl FromRow for HashMap<String, Value> {
+  fn from_row(row: Row) -> Result<Self, ConversionError> {
+    Ok(row.into_map())
+  }
+}
+
+macro_rules! tuple_from_row {
+  ($len:expr; $($t:ident $i:tt),+) => {
+    impl<$($t: FromValue),+> FromRow for ($($t,)+) {
+      fn from_row(row: Row) -> Result<Self, ConversionError> {
+        if row.fields.len() != $len {
+          return Err(ConversionError::LengthMismatch {
+            expected: $len,
+            found: row.fields.len()
+          });
+        }
+        let keys = row.keys;
+        let mut fields = row.fields.into_iter();
+        Ok(($(
+          $t::from_value(fields.next().unwrap_or(Value::Null))
+            .map_err(|e| match keys.get($i) {
+              Some(key) => e.in_key(key),
+              None => e.in_index($i)
+            })?,
+        )+))
+      }
+    }
+  }
+}
+
+tuple_from_row!(1; A 0);
+tuple_from_row!(2; A 0, B 1);
+tuple_from_row!(3; A 0, B 1, C 2);
+tuple_from_row!(4; A 0, B 1, C 2, D 3);
+tuple_from_row!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_from_row!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_from_row!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_from_row!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+tuple_from_row!(9; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+tuple_from_row!(10; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+tuple_from_row!(11; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+tuple_from_row!(12; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+impl Record {
+  /// Gets the field of the column at `index`, converted to `T`.
+  pub fn get<T: FromValue>(&self, index: usize) -> Result<T, ConversionError> {
+    let value = self.fields
+      .get(index)
+      .cloned()
+      .ok_or(ConversionError::LengthMismatch {
+        expected: index + 1,
+        found: self.fields.len()
+      })?;
+    T::from_value(value).map_err(|e| e.in_index(index))
+  }
+}
+
+impl Success {
+  /**
+   * Names of the columns of a result, listed by the `fields` metadata of the
+   * `Success` answering `Run`. `None` when the metadata is missing or is not
+   * a list of strings.
+   */
+  pub fn fields(&self) -> Option<Arc<[String]>> {
+    match self.metadata.get("fields") {
+      Some(Value::List(xs)) => xs
+        .iter()
+        .map(|x| match x {
+          Value::String(s) => Some(s.clone()),
+          _ => None
+        })
+        .collect(),
+      _ => None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use packstream_proc::FromRow;
+  use super::*;
+
+  #[derive(Debug, PartialEq, FromRow)]
+  struct Person {
+    name: String,
+    #[row(rename = "n.age")]
+    age: i64
+  }
+
+  fn row() -> Row {
+    let keys: Arc<[String]> = Arc::from(vec!["name".to_owned(), "n.age".to_owned()]);
+    Row::new(keys, vec![Value::String("Alice".to_owned()), Value::Int(42)])
+  }
+
+  #[test]
+  fn gets_columns_by_name() {
+    assert_eq!(row().get::<i64>("n.age"), Ok(42));
+    assert_eq!(row().get::<i64>("age"), Err(ConversionError::MissingKey("age".to_owned())));
+    assert!(matches!(row().get::<i64>("name"), Err(ConversionError::InKey(key, _)) if key == "name"));
+  }
+
+  #[test]
+  fn converts_rows() {
+    assert_eq!(row().into_typed::<(String, i64)>(), Ok(("Alice".to_owned(), 42)));
+    assert_eq!(
+      row().into_typed::<(String,)>(),
+      Err(ConversionError::LengthMismatch { expected: 1, found: 2 })
+    );
+    assert_eq!(row().into_typed::<Person>(), Ok(Person { name: "Alice".to_owned(), age: 42 }));
+  }
+
+  #[test]
+  fn gets_record_fields_by_index() {
+    let record = Record { fields: row().into_fields() };
+    assert_eq!(record.get::<String>(0), Ok("Alice".to_owned()));
+    assert_eq!(record.get::<i64>(2), Err(ConversionError::LengthMismatch { expected: 3, found: 2 }));
+    assert!(matches!(record.get::<String>(1), Err(ConversionError::InIndex(1, _))));
+  }
+}
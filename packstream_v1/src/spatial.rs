@@ -0,0 +1,99 @@
+//! Spatial structures, available since Bolt 2.
+//!
+//! Points hold the SRID of their coordinate reference system with their
+//! coordinates. Neo4j knows four systems: [WGS84] and [WGS84_3D], whose `x`,
+//! `y` and `z` are the longitude, latitude and height, and [CARTESIAN] and
+//! [CARTESIAN_3D]. Each has its constructor, such as [Point2D::wgs84].
+//!
+//! The `geo-types` feature converts 2D points to and from `geo_types::Point`,
+//! which has no SRID: it is dropped one way and given the other.
+use packstream_proc::BoltStruct;
+
+#[cfg(feature = "geo-types")]
+mod geo;
+
+pub const WGS84: i64 = 4326;
+pub const WGS84_3D: i64 = 4979;
+pub const CARTESIAN: i64 = 7203;
+pub const CARTESIAN_3D: i64 = 9157;
+
+#[derive(Clone, Copy, Debug, PartialEq, BoltStruct)]
+#[bolt(signature = 0x58)]
+pub struct Point2D {
+  pub srid: i64,
+  pub x: f64,
+  pub y: f64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, BoltStruct)]
+#[bolt(signature = 0x59)]
+pub struct Point3D {
+  pub srid: i64,
+  pub x: f64,
+  pub y: f64,
+  pub z: f64
+}
+
+impl Point2D {
+  pub fn new(srid: i64, x: f64, y: f64) -> Self {
+    Point2D { srid, x, y }
+  }
+
+  pub fn wgs84(longitude: f64, latitude: f64) -> Self {
+    Point2D::new(WGS84, longitude, latitude)
+  }
+
+  pub fn cartesian(x: f64, y: f64) -> Self {
+    Point2D::new(CARTESIAN, x, y)
+  }
+}
+
+impl Point3D {
+  pub fn new(srid: i64, x: f64, y: f64, z: f64) -> Self {
+    Point3D { srid, x, y, z }
+  }
+
+  pub fn wgs84(longitude: f64, latitude: f64, height: f64) -> Self {
+    Point3D::new(WGS84_3D, longitude, latitude, height)
+  }
+
+  pub fn cartesian(x: f64, y: f64, z: f64) -> Self {
+    Point3D::new(CARTESIAN_3D, x, y, z)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use packstream_core::packer::{Packer, PackValue};
+  use packstream_core::unpacker::{Unpacker, UnpackValue};
+  use crate::structs::Struct;
+  use super::*;
+
+  fn round_trip(s: Struct) -> (Vec<u8>, Struct) {
+    let mut packer = Packer::new();
+    packer.pack(s).unwrap();
+    let bytes = packer[..].to_vec();
+    (bytes.clone(), Unpacker::new(bytes).unpack().unwrap())
+  }
+
+  #[test]
+  fn packs_points() {
+    let point = Struct::Point2D(Point2D::cartesian(1.0, 2.0));
+    let (bytes, unpacked) = round_trip(point.clone());
+    assert_eq!(bytes[..4], [0xB3, 0x58, 0xC9, 0x1C]);
+    assert_eq!(unpacked, point);
+
+    let point = Struct::Point3D(Point3D::wgs84(2.35, 48.85, 35.0));
+    let (bytes, unpacked) = round_trip(point.clone());
+    assert_eq!(bytes[..5], [0xB4, 0x59, 0xC9, 0x13, 0x73]);
+    assert_eq!(unpacked, point);
+  }
+
+  #[test]
+  fn sets_srids() {
+    assert_eq!(Point2D::wgs84(2.35, 48.85), Point2D { srid: 4326, x: 2.35, y: 48.85 });
+    assert_eq!(Point2D::cartesian(1.0, 2.0).srid, 7203);
+    assert_eq!(Point3D::wgs84(2.35, 48.85, 35.0).srid, 4979);
+    assert_eq!(Point3D::cartesian(1.0, 2.0, 3.0), Point3D { srid: 9157, x: 1.0, y: 2.0, z: 3.0 });
+  }
+}
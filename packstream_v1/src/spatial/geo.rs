@@ -0,0 +1,37 @@
+//! Conversions with the types of `geo-types`.
+use super::*;
+
+impl From<Point2D> for geo_types::Point<f64> {
+  fn from(x: Point2D) -> Self {
+    geo_types::Point::new(x.x, x.y)
+  }
+}
+
+impl From<Point2D> for geo_types::Coord<f64> {
+  fn from(x: Point2D) -> Self {
+    geo_types::Coord { x: x.x, y: x.y }
+  }
+}
+
+impl Point2D {
+  /// Creates a point of the coordinate reference system `srid`, such as
+  /// [WGS84] for longitudes and latitudes.
+  pub fn from_geo<P: Into<geo_types::Coord<f64>>>(point: P, srid: i64) -> Self {
+    let coord = point.into();
+    Point2D::new(srid, coord.x, coord.y)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn converts_geo_types_points() {
+    let point = Point2D::wgs84(2.35, 48.85);
+    assert_eq!(geo_types::Point::from(point), geo_types::Point::new(2.35, 48.85));
+    assert_eq!(geo_types::Coord::from(point), geo_types::Coord { x: 2.35, y: 48.85 });
+    assert_eq!(Point2D::from_geo(geo_types::Point::new(2.35, 48.85), WGS84), point);
+    assert_eq!(Point2D::from_geo((1.0, 2.0), CARTESIAN), Point2D::cartesian(1.0, 2.0));
+  }
+}
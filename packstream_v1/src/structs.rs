@@ -1,48 +1,415 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
-use packstream_proc::{bolt_packstream};
-use packstream_core::packer::{PackValue,Packer};
-use packstream_core::unpacker::{UnpackValue,Unpacker};
-use packstream_core::error::BoltError;
+use packstream_proc::{BoltStruct, bolt_enum};
 use crate::value::Value;
+use crate::convert::{self, ConversionError, FromValue};
+use crate::registry::CustomStruct;
+use crate::temporal::*;
+use crate::spatial::{Point2D, Point3D};
 
-#[derive(Debug, PartialEq)]
+#[bolt_enum(fallback = "fallback")]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Struct {
   Node(Node),
   Relationship(Relationship),
   Path(Path),
-  UnboundRelationship(UnboundRelationship)
+  UnboundRelationship(UnboundRelationship),
+  Date(Date),
+  Time(Time),
+  LocalTime(LocalTime),
+  /// Packed as a [LegacyDateTime] before Bolt 5 without the `utc` patch.
+  #[fallback]
+  DateTime(DateTime),
+  /// Only packed as of Bolt 5 or with the `utc` patch.
+  #[fallback]
+  DateTimeZoneId(DateTimeZoneId),
+  /// Packed as a [DateTime] as of Bolt 5 or with the `utc` patch.
+  #[fallback]
+  LegacyDateTime(LegacyDateTime),
+  /// Only packed before Bolt 5 without the `utc` patch.
+  #[fallback]
+  LegacyDateTimeZoneId(LegacyDateTimeZoneId),
+  LocalDateTime(LocalDateTime),
+  Duration(Duration),
+  Point2D(Point2D),
+  Point3D(Point3D),
+  /// Structure whose signature is not known by this crate. Its fields are
+  /// kept as plain values so that it can be packed back unchanged.
+  #[fallback]
+  Unknown { signature: u8, fields: Vec<Value> },
+  /// Structure registered by the application, see [registry](crate::registry).
+  #[fallback]
+  Custom { signature: u8, value: Box<dyn CustomStruct> }
 }
 
-#[bolt_packstream(0x4E)]
-#[derive(Debug, PartialEq)]
+/// Structures of [Struct] whose encoding is not fixed by their signature.
+mod fallback {
+  use packstream_core::packer::{Packer, PackValue};
+  use packstream_core::unpacker::Unpacker;
+  use packstream_core::error::BoltError;
+  use packstream_core::structure::BoltStructure;
+  use crate::temporal::{DateTime, DateTimeZoneId, LegacyDateTime, LegacyDateTimeZoneId};
+  use crate::unpacker::unpack_unknown_struct;
+  use crate::registry;
+  use super::Struct;
+
+  /**
+   * Packs date times with the encoding of the protocol, see
+   * [utc_date_times](Packer::utc_date_times). A date time with a zone id
+   * cannot be converted without the zone rules, and fails with
+   * [PackUnsupportedStructSignature](BoltError::PackUnsupportedStructSignature)
+   * when its encoding is not the one of the protocol.
+   */
+  pub fn pack(packer: &mut Packer, s: Struct) -> Result<(), BoltError> {
+    let utc = packer.utc_date_times();
+    match s {
+      Struct::DateTime(x) if utc => packer.pack(x),
+      Struct::DateTime(x) => packer.pack(LegacyDateTime::from(x)),
+      Struct::LegacyDateTime(x) if utc => packer.pack(DateTime::from(x)),
+      Struct::LegacyDateTime(x) => packer.pack(x),
+      Struct::DateTimeZoneId(x) if utc => packer.pack(x),
+      Struct::LegacyDateTimeZoneId(x) if !utc => packer.pack(x),
+      Struct::DateTimeZoneId(_) => unsupported::<DateTimeZoneId>(),
+      Struct::LegacyDateTimeZoneId(_) => unsupported::<LegacyDateTimeZoneId>(),
+      Struct::Unknown { signature, fields } => packer
+        .pack_struct_header(fields.len(), signature)
+        .and_then(|_| fields
+          .into_iter()
+          .try_for_each(|x| packer.pack(x))
+        ),
+      Struct::Custom { signature, value } => registry::dehydrator(signature)
+        .ok_or(BoltError::PackUnregisteredStructSignature)
+        .and_then(|dehydrate| dehydrate(packer, value)),
+      _ => Err(BoltError::Unmanaged)
+    }
+  }
+
+  fn unsupported<T: BoltStructure>() -> Result<(), BoltError> {
+    Err(BoltError::PackUnsupportedStructSignature { signature: T::SIGNATURE })
+  }
+
+  /// Hydrates registered structures, keeps the others as [Struct::Unknown].
+  pub fn unpack(unpacker: &mut Unpacker, signature: u8) -> Result<Struct, BoltError> {
+    match registry::hydrator(signature) {
+      Some(hydrate) => hydrate(unpacker).map(|value| Struct::Custom { signature, value }),
+      None => unpack_unknown_struct(unpacker)
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, BoltStruct)]
+#[bolt(signature = 0x4E)]
 pub struct Node {
   pub id: i64,
   pub labels: Vec<String>,
-  pub properties: HashMap<String, Value>
+  pub properties: HashMap<String, Value>,
+  #[bolt(since = "5.0")]
+  pub element_id: Option<String>
 }
 
-#[bolt_packstream(0x52)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, BoltStruct)]
+#[bolt(signature = 0x52)]
 pub struct Relationship {
   pub id: i64,
   pub start_node_id: i64,
   pub end_node_id: i64,
   pub type_name: String,
-  pub properties: HashMap<String, Value>
+  pub properties: HashMap<String, Value>,
+  #[bolt(since = "5.0")]
+  pub element_id: Option<String>,
+  #[bolt(since = "5.0")]
+  pub start_node_element_id: Option<String>,
+  #[bolt(since = "5.0")]
+  pub end_node_element_id: Option<String>
 }
 
-#[bolt_packstream(0x50)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, BoltStruct)]
+#[bolt(signature = 0x50)]
 pub struct Path {
   pub nodes: Vec<Node>,
   pub relationships: Vec<UnboundRelationship>,
   pub sequence: Vec<i64>,
 }
 
-#[bolt_packstream(0x72)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, BoltStruct)]
+#[bolt(signature = 0x72)]
 pub struct UnboundRelationship {
   pub id: i64,
   pub type_name: String,
-  pub properties: HashMap<String, Value>
+  pub properties: HashMap<String, Value>,
+  #[bolt(since = "5.0")]
+  pub element_id: Option<String>
+}
+
+/**
+ * Graph accessors. Properties are converted with [FromValue], a missing
+ * property failing with [MissingKey](ConversionError::MissingKey) unless
+ * converted to an `Option`.
+ */
+impl Node {
+  pub fn has_label(&self, label: &str) -> bool {
+    self.labels.iter().any(|l| l == label)
+  }
+
+  pub fn get<T: FromValue>(&self, key: &str) -> Result<T, ConversionError> {
+    convert::get(&self.properties, key)
+  }
+}
+
+impl Relationship {
+  pub fn type_name(&self) -> &str {
+    &self.type_name
+  }
+
+  pub fn get<T: FromValue>(&self, key: &str) -> Result<T, ConversionError> {
+    convert::get(&self.properties, key)
+  }
+
+  /// Id of the node at the other end from `node_id`, `None` when the
+  /// relationship doesn't touch that node.
+  pub fn other_end(&self, node_id: i64) -> Option<i64> {
+    if self.start_node_id == node_id {
+      Some(self.end_node_id)
+    }
+    else if self.end_node_id == node_id {
+      Some(self.start_node_id)
+    }
+    else {
+      None
+    }
+  }
+
+  /// Element id of the node at the other end from `element_id`, matched as
+  /// [element_id](Node::element_id) is, whatever the protocol version.
+  pub fn other_end_element_id(&self, element_id: &str) -> Option<Cow<'_, str>> {
+    if self.start_node_element_id() == element_id {
+      Some(self.end_node_element_id())
+    }
+    else if self.end_node_element_id() == element_id {
+      Some(self.start_node_element_id())
+    }
+    else {
+      None
+    }
+  }
+}
+
+impl UnboundRelationship {
+  pub fn type_name(&self) -> &str {
+    &self.type_name
+  }
+
+  pub fn get<T: FromValue>(&self, key: &str) -> Result<T, ConversionError> {
+    convert::get(&self.properties, key)
+  }
+
+  /// Binds the relationship to the nodes it goes from and to.
+  pub fn bind(self, start: &Node, end: &Node) -> Relationship {
+    Relationship {
+      id: self.id,
+      start_node_id: start.id,
+      end_node_id: end.id,
+      type_name: self.type_name,
+      properties: self.properties,
+      element_id: self.element_id,
+      start_node_element_id: start.element_id.clone(),
+      end_node_element_id: end.element_id.clone()
+    }
+  }
+}
+
+/**
+ * Element ids, sent as of Bolt 5 alongside the integer ids they deprecate.
+ * The accessors fall back to the integer ids written in decimal when the
+ * server did not send element ids, so that an entity can be told apart
+ * whatever the protocol version.
+ */
+impl Node {
+  pub fn element_id(&self) -> Cow<'_, str> {
+    element_id(&self.element_id, self.id)
+  }
+}
+
+impl Relationship {
+  pub fn element_id(&self) -> Cow<'_, str> {
+    element_id(&self.element_id, self.id)
+  }
+
+  pub fn start_node_element_id(&self) -> Cow<'_, str> {
+    element_id(&self.start_node_element_id, self.start_node_id)
+  }
+
+  pub fn end_node_element_id(&self) -> Cow<'_, str> {
+    element_id(&self.end_node_element_id, self.end_node_id)
+  }
+}
+
+impl UnboundRelationship {
+  pub fn element_id(&self) -> Cow<'_, str> {
+    element_id(&self.element_id, self.id)
+  }
+}
+
+fn element_id(element_id: &Option<String>, id: i64) -> Cow<'_, str> {
+  match element_id {
+    Some(element_id) => Cow::Borrowed(element_id),
+    None => Cow::Owned(id.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use packstream_core::packer::{Packer, PackValue};
+  use packstream_core::unpacker::{Unpacker, UnpackValue};
+  use packstream_core::error::BoltError;
+  use packstream_proc::bolt_packstream;
+  use super::*;
+
+  #[bolt_packstream(0x70)]
+  #[derive(Debug, PartialEq)]
+  struct Cached {
+    key: String,
+    #[bolt(skip)]
+    hits: i64
+  }
+
+  #[bolt_packstream(0x71, skip_extra)]
+  #[derive(Debug, PartialEq)]
+  struct Head {
+    first: i64
+  }
+
+  #[derive(Debug, PartialEq, BoltStruct)]
+  #[bolt(signature = 0x72)]
+  struct Tail {
+    first: i64,
+    #[bolt(optional)]
+    second: i64
+  }
+
+  fn unknown(signature: u8, fields: Vec<i64>) -> Struct {
+    Struct::Unknown { signature, fields: fields.into_iter().map(Value::Int).collect() }
+  }
+
+  fn unpack_packed<T>(values: Vec<Struct>) -> (Result<T, BoltError>, Unpacker)
+    where Unpacker: UnpackValue<T> {
+    let mut packer = Packer::new();
+    values.into_iter().for_each(|x| packer.pack(x).unwrap());
+    let mut unpacker = Unpacker::new(packer[..].to_vec());
+    (unpacker.unpack(), unpacker)
+  }
+
+  fn round_trip(s: Struct) -> Struct {
+    let mut packer = Packer::new();
+    packer.pack(s).unwrap();
+    Unpacker::new(packer[..].to_vec()).unpack().unwrap()
+  }
+
+  #[test]
+  fn round_trips_known_structures() {
+    let node = Struct::Node(Node {
+      id: 1,
+      labels: vec!["Person".to_owned()],
+      properties: HashMap::new(),
+      element_id: None
+    });
+    assert_eq!(round_trip(node), Struct::Node(Node {
+      id: 1,
+      labels: vec!["Person".to_owned()],
+      properties: HashMap::new(),
+      element_id: None
+    }));
+  }
+
+  #[test]
+  fn sizes_structures_per_version() {
+    use packstream_core::structure::BoltStructure;
+    use packstream_core::version::Version;
+
+    assert_eq!(Node::struct_size(), 3);
+    assert_eq!(Node::size_at(Version::V4_4), 3);
+    assert_eq!(Node::size_at(Version::V5_0), 4);
+    assert_eq!(Node::SIZE, 4);
+    assert_eq!(Relationship::struct_size(), 5);
+    assert_eq!(Relationship::fields_at(Version::V5_0).len(), Relationship::size_at(Version::V5_0));
+  }
+
+  #[test]
+  fn keeps_unknown_structures() {
+    let unknown = || Struct::Unknown { signature: 0x20, fields: vec![Value::Int(1), Value::Int(2)] };
+    assert_eq!(round_trip(unknown()), unknown());
+  }
+
+  #[test]
+  fn strips_field_attributes_of_bolt_packstream() {
+    let mut packer = Packer::new();
+    packer.pack(Cached { key: "k".to_owned(), hits: 3 }).unwrap();
+    let cached: Cached = Unpacker::new(packer[..].to_vec()).unpack().unwrap();
+    assert_eq!(cached, Cached { key: "k".to_owned(), hits: 0 });
+  }
+
+  #[test]
+  fn fails_on_another_signature() {
+    let relationship = unknown(0x52, vec![1, 2, 3]);
+    assert!(matches!(
+      unpack_packed::<Node>(vec![relationship]).0,
+      Err(BoltError::UnpackUnexpectedStructSignature { expected: 0x4E, found: 0x52 })
+    ));
+  }
+
+  #[test]
+  fn fails_on_another_field_count() {
+    assert!(matches!(
+      unpack_packed::<Node>(vec![unknown(0x4E, vec![1, 2])]).0,
+      Err(BoltError::UnpackStructFieldCountMismatch { min: 3, max: 3, found: 2 })
+    ));
+    assert!(matches!(
+      unpack_packed::<Tail>(vec![unknown(0x72, vec![1, 2, 3])]).0,
+      Err(BoltError::UnpackStructFieldCountMismatch { min: 1, max: 2, found: 3 })
+    ));
+  }
+
+  #[test]
+  fn defaults_missing_optional_fields() {
+    let (tail, _) = unpack_packed::<Tail>(vec![unknown(0x72, vec![1])]);
+    assert_eq!(tail.unwrap(), Tail { first: 1, second: 0 });
+  }
+
+  #[test]
+  fn skips_extra_fields() {
+    let (head, mut unpacker) = unpack_packed::<Head>(vec![
+      unknown(0x71, vec![1, 2, 3]),
+      unknown(0x71, vec![4])
+    ]);
+    assert_eq!(head.unwrap(), Head { first: 1 });
+    assert_eq!(unpacker.unpack().ok(), Some(Head { first: 4 }));
+  }
+
+  #[test]
+  fn finds_the_other_end() {
+    let node = |id: i64, element_id: Option<&str>| Node {
+      id,
+      labels: vec![],
+      properties: HashMap::new(),
+      element_id: element_id.map(str::to_owned)
+    };
+    let unbound = UnboundRelationship {
+      id: 10,
+      type_name: "KNOWS".to_owned(),
+      properties: HashMap::new(),
+      element_id: None
+    };
+
+    let legacy = unbound.clone().bind(&node(1, None), &node(2, None));
+    assert_eq!(legacy.other_end(1), Some(2));
+    assert_eq!(legacy.other_end(2), Some(1));
+    assert_eq!(legacy.other_end(3), None);
+    assert_eq!(legacy.other_end_element_id("1").as_deref(), Some("2"));
+
+    let knows = unbound.bind(&node(1, Some("4:db:1")), &node(2, Some("4:db:2")));
+    assert_eq!(knows.other_end(1), Some(2));
+    assert_eq!(knows.other_end_element_id("4:db:1").as_deref(), Some("4:db:2"));
+    assert_eq!(knows.other_end_element_id("4:db:2").as_deref(), Some("4:db:1"));
+    assert_eq!(knows.other_end_element_id("4:other:1"), None);
+  }
 }
@@ -0,0 +1,437 @@
+//! Temporal structures, available since Bolt 2.
+//!
+//! Each type holds the fields of its structure as sent on the wire. Seconds
+//! and days count from the Unix epoch, nanoseconds are those of the second,
+//! or of the day for times.
+//!
+//! Date times come in two encodings. Bolt 5 sends [DateTime] and
+//! [DateTimeZoneId], whose seconds are those of the UTC instant. Earlier
+//! versions send [LegacyDateTime] and [LegacyDateTimeZoneId], whose seconds
+//! are those of the local date time, unless the server accepted the `utc`
+//! patch of Bolt 4.3 and 4.4, set with
+//! [set_utc_patch](packstream_core::packer::Packer::set_utc_patch). Date
+//! times with an offset packed as a [Struct](crate::structs::Struct) are
+//! converted to the encoding of the protocol. Zone ids can't be converted
+//! without a time zone database: packing one in the other encoding fails
+//! with `BoltError::PackUnsupportedStructSignature`.
+//!
+//! All types display as ISO 8601 strings, such as `2021-03-04T05:06:07+01:00`
+//! or `P1Y2M3DT4H5M6.5S`, date times with a zone id followed by the zone in
+//! brackets. [DateTimeZoneId] only knows its UTC instant and displays it
+//! with `Z` before the zone.
+//!
+//! The `chrono` and `time` features convert these types to and from those
+//! of the matching crates, failing with a [TemporalError] when a value has
+//! no equivalent.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Write};
+use packstream_proc::BoltStruct;
+
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "time")]
+mod time;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x44)]
+pub struct Date {
+  pub days: i64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x54)]
+pub struct Time {
+  pub nanoseconds: i64,
+  pub tz_offset_seconds: i64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x74)]
+pub struct LocalTime {
+  pub nanoseconds: i64
+}
+
+/// Date time with an offset, `seconds` being those of the UTC instant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x49)]
+pub struct DateTime {
+  pub seconds: i64,
+  pub nanoseconds: i64,
+  pub tz_offset_seconds: i64
+}
+
+/// Date time with a zone id, `seconds` being those of the UTC instant.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x69)]
+pub struct DateTimeZoneId {
+  pub seconds: i64,
+  pub nanoseconds: i64,
+  pub tz_id: String
+}
+
+/// Date time with an offset as sent before Bolt 5, `seconds` being those of
+/// the local date time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x46)]
+pub struct LegacyDateTime {
+  pub seconds: i64,
+  pub nanoseconds: i64,
+  pub tz_offset_seconds: i64
+}
+
+/// Date time with a zone id as sent before Bolt 5, `seconds` being those of
+/// the local date time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x66)]
+pub struct LegacyDateTimeZoneId {
+  pub seconds: i64,
+  pub nanoseconds: i64,
+  pub tz_id: String
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x64)]
+pub struct LocalDateTime {
+  pub seconds: i64,
+  pub nanoseconds: i64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BoltStruct)]
+#[bolt(signature = 0x45)]
+pub struct Duration {
+  pub months: i64,
+  pub days: i64,
+  pub seconds: i64,
+  pub nanoseconds: i64
+}
+
+impl From<LegacyDateTime> for DateTime {
+  fn from(x: LegacyDateTime) -> Self {
+    DateTime {
+      seconds: x.seconds.wrapping_sub(x.tz_offset_seconds),
+      nanoseconds: x.nanoseconds,
+      tz_offset_seconds: x.tz_offset_seconds
+    }
+  }
+}
+
+impl From<DateTime> for LegacyDateTime {
+  fn from(x: DateTime) -> Self {
+    LegacyDateTime {
+      seconds: x.seconds.wrapping_add(x.tz_offset_seconds),
+      nanoseconds: x.nanoseconds,
+      tz_offset_seconds: x.tz_offset_seconds
+    }
+  }
+}
+
+/// Error converting a temporal structure to or from another crate's type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemporalError {
+  /// The value is out of the range of the target type, named.
+  OutOfRange(&'static str),
+  /// Offset in seconds the target type can't represent.
+  InvalidOffset(i64),
+  /// Zone id unknown to the time zone database.
+  UnknownZone(String),
+  /// Local date time skipped or repeated in its zone, e.g. by a daylight
+  /// saving time change.
+  AmbiguousLocalDateTime,
+  /// Duration counting months, whose length depends on the date it applies
+  /// to and has no exact equivalent.
+  DurationWithMonths(i64)
+}
+
+impl fmt::Display for TemporalError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TemporalError::OutOfRange(target) => write!(f, "value out of the range of {}", target),
+      TemporalError::InvalidOffset(offset) => write!(f, "invalid offset of {} seconds", offset),
+      TemporalError::UnknownZone(zone) => write!(f, "unknown time zone {:?}", zone),
+      TemporalError::AmbiguousLocalDateTime =>
+        write!(f, "local date time skipped or repeated in its zone"),
+      TemporalError::DurationWithMonths(months) =>
+        write!(f, "duration of {} months has no exact equivalent", months)
+    }
+  }
+}
+
+impl Error for TemporalError {}
+
+/// Splits a count of nanoseconds in seconds and nanoseconds of the second.
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn split_nanos(nanos: i128) -> Option<(i64, u32)> {
+  let seconds = i64::try_from(nanos.div_euclid(i128::from(NANOS_PER_SECOND))).ok()?;
+  Some((seconds, nanos.rem_euclid(i128::from(NANOS_PER_SECOND)) as u32))
+}
+
+/// Total nanoseconds of a duration without months, days counting 24 hours.
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn duration_nanos(x: &Duration) -> Result<i128, TemporalError> {
+  if x.months != 0 {
+    return Err(TemporalError::DurationWithMonths(x.months));
+  }
+  let seconds = i128::from(x.days) * i128::from(SECONDS_PER_DAY) + i128::from(x.seconds);
+  Ok(seconds * i128::from(NANOS_PER_SECOND) + i128::from(x.nanoseconds))
+}
+
+/// Year, month and day of the `days`th day since the Unix epoch, in the
+/// proleptic Gregorian calendar.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let z = i128::from(days) + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i128::from(month <= 2);
+  (i64::try_from(year).unwrap_or(i64::MAX), month as u32, day as u32)
+}
+
+fn write_date(f: &mut fmt::Formatter, days: i64) -> fmt::Result {
+  let (year, month, day) = civil_from_days(days);
+  if (0..=9999).contains(&year) {
+    write!(f, "{:04}-{:02}-{:02}", year, month, day)
+  }
+  else {
+    write!(f, "{:+05}-{:02}-{:02}", year, month, day)
+  }
+}
+
+/// Writes the fraction of a second, if any, without trailing zeros.
+fn write_fraction(f: &mut fmt::Formatter, nanoseconds: i64) -> fmt::Result {
+  if nanoseconds == 0 {
+    return Ok(());
+  }
+  let digits = format!("{:09}", nanoseconds);
+  write!(f, ".{}", digits.trim_end_matches('0'))
+}
+
+fn write_time(f: &mut fmt::Formatter, nanoseconds: i64) -> fmt::Result {
+  let seconds = nanoseconds.div_euclid(NANOS_PER_SECOND);
+  write!(f, "{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)?;
+  write_fraction(f, nanoseconds.rem_euclid(NANOS_PER_SECOND))
+}
+
+fn write_offset(f: &mut fmt::Formatter, offset: i64) -> fmt::Result {
+  if offset == 0 {
+    return f.write_char('Z');
+  }
+  let sign = if offset < 0 { '-' } else { '+' };
+  let offset = offset.unsigned_abs();
+  write!(f, "{}{:02}:{:02}", sign, offset / 3600, offset / 60 % 60)?;
+  match offset % 60 {
+    0 => Ok(()),
+    seconds => write!(f, ":{:02}", seconds)
+  }
+}
+
+fn write_date_time(f: &mut fmt::Formatter, seconds: i64, nanoseconds: i64) -> fmt::Result {
+  write_date(f, seconds.div_euclid(SECONDS_PER_DAY))?;
+  f.write_char('T')?;
+  let time = seconds.rem_euclid(SECONDS_PER_DAY);
+  write_time(f, (time * NANOS_PER_SECOND).saturating_add(nanoseconds))
+}
+
+impl fmt::Display for Date {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_date(f, self.days)
+  }
+}
+
+impl fmt::Display for Time {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_time(f, self.nanoseconds)?;
+    write_offset(f, self.tz_offset_seconds)
+  }
+}
+
+impl fmt::Display for LocalTime {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_time(f, self.nanoseconds)
+  }
+}
+
+impl fmt::Display for DateTime {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", LegacyDateTime::from(*self))
+  }
+}
+
+impl fmt::Display for LegacyDateTime {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_date_time(f, self.seconds, self.nanoseconds)?;
+    write_offset(f, self.tz_offset_seconds)
+  }
+}
+
+impl fmt::Display for DateTimeZoneId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_date_time(f, self.seconds, self.nanoseconds)?;
+    write!(f, "Z[{}]", self.tz_id)
+  }
+}
+
+impl fmt::Display for LegacyDateTimeZoneId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_date_time(f, self.seconds, self.nanoseconds)?;
+    write!(f, "[{}]", self.tz_id)
+  }
+}
+
+impl fmt::Display for LocalDateTime {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_date_time(f, self.seconds, self.nanoseconds)
+  }
+}
+
+/// Writes `P1Y2M3DT4H5M6.5S`, months being split in years and months, and
+/// seconds in hours, minutes and seconds.
+impl fmt::Display for Duration {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("P")?;
+    let (years, months) = (self.months / 12, self.months % 12);
+    for (n, unit) in [(years, 'Y'), (months, 'M'), (self.days, 'D')].iter() {
+      if *n != 0 {
+        write!(f, "{}{}", n, unit)?;
+      }
+    }
+    let nanos = i128::from(self.seconds) * i128::from(NANOS_PER_SECOND) + i128::from(self.nanoseconds);
+    if nanos == 0 {
+      return if self.months == 0 && self.days == 0 { f.write_str("T0S") } else { Ok(()) };
+    }
+    f.write_str("T")?;
+    let sign = if nanos < 0 { "-" } else { "" };
+    let nanos = nanos.unsigned_abs();
+    let seconds = nanos / NANOS_PER_SECOND as u128;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours != 0 {
+      write!(f, "{}{}H", sign, hours)?;
+    }
+    if minutes != 0 {
+      write!(f, "{}{}M", sign, minutes)?;
+    }
+    let fraction = (nanos % NANOS_PER_SECOND as u128) as i64;
+    if seconds != 0 || fraction != 0 {
+      write!(f, "{}{}", sign, seconds)?;
+      write_fraction(f, fraction)?;
+      f.write_str("S")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use packstream_core::error::BoltError;
+  use packstream_core::packer::{Packer, PackValue};
+  use packstream_core::unpacker::{Unpacker, UnpackValue};
+  use packstream_core::version::Version;
+  use crate::structs::Struct;
+  use super::*;
+
+  /// 2021-03-04T05:06:07.5+01:00
+  const DATE_TIME: DateTime = DateTime {
+    seconds: 1_614_830_767,
+    nanoseconds: 500_000_000,
+    tz_offset_seconds: 3600
+  };
+  const LEGACY_DATE_TIME: LegacyDateTime = LegacyDateTime {
+    seconds: 1_614_834_367,
+    nanoseconds: 500_000_000,
+    tz_offset_seconds: 3600
+  };
+
+  fn zone_id() -> DateTimeZoneId {
+    DateTimeZoneId { seconds: 1_614_830_767, nanoseconds: 0, tz_id: "Europe/Paris".to_owned() }
+  }
+
+  fn legacy_zone_id() -> LegacyDateTimeZoneId {
+    LegacyDateTimeZoneId { seconds: 1_614_834_367, nanoseconds: 0, tz_id: "Europe/Paris".to_owned() }
+  }
+
+  /// Packs `s` for `version`, then unpacks it.
+  fn round_trip(version: Version, utc_patch: bool, s: Struct) -> Result<Struct, BoltError> {
+    let mut packer = Packer::with_version(version);
+    packer.set_utc_patch(utc_patch);
+    packer.pack(s)?;
+    Unpacker::with_version(packer[..].to_vec(), version).unpack()
+  }
+
+  #[test]
+  fn converts_legacy_date_times() {
+    assert_eq!(LegacyDateTime::from(DATE_TIME), LEGACY_DATE_TIME);
+    assert_eq!(DateTime::from(LEGACY_DATE_TIME), DATE_TIME);
+  }
+
+  #[test]
+  fn packs_date_times_with_the_encoding_of_the_protocol() {
+    let date_time = Struct::DateTime(DATE_TIME);
+    let legacy = Struct::LegacyDateTime(LEGACY_DATE_TIME);
+    for s in [date_time.clone(), legacy.clone()].iter() {
+      assert_eq!(round_trip(Version::V4_4, false, s.clone()).ok(), Some(legacy.clone()));
+      assert_eq!(round_trip(Version::V4_4, true, s.clone()).ok(), Some(date_time.clone()));
+      assert_eq!(round_trip(Version::V5_0, false, s.clone()).ok(), Some(date_time.clone()));
+    }
+  }
+
+  #[test]
+  fn packs_zone_ids_only_in_their_encoding() {
+    let zone_id = Struct::DateTimeZoneId(zone_id());
+    let legacy = Struct::LegacyDateTimeZoneId(legacy_zone_id());
+    assert_eq!(round_trip(Version::V4_4, false, legacy.clone()).ok(), Some(legacy.clone()));
+    assert_eq!(round_trip(Version::V4_4, true, zone_id.clone()).ok(), Some(zone_id.clone()));
+    assert_eq!(round_trip(Version::V5_0, false, zone_id.clone()).ok(), Some(zone_id.clone()));
+    assert!(matches!(
+      round_trip(Version::V4_4, false, zone_id),
+      Err(BoltError::PackUnsupportedStructSignature { signature: 0x69 })
+    ));
+    assert!(matches!(
+      round_trip(Version::V4_4, true, legacy.clone()),
+      Err(BoltError::PackUnsupportedStructSignature { signature: 0x66 })
+    ));
+    assert!(matches!(
+      round_trip(Version::V5_0, false, legacy),
+      Err(BoltError::PackUnsupportedStructSignature { signature: 0x66 })
+    ));
+  }
+
+  #[test]
+  fn round_trips_other_temporal_structures() {
+    let values = vec![
+      Struct::Date(Date { days: -1 }),
+      Struct::Time(Time { nanoseconds: 1, tz_offset_seconds: -3600 }),
+      Struct::LocalTime(LocalTime { nanoseconds: 86_399_999_999_999 }),
+      Struct::LocalDateTime(LocalDateTime { seconds: -1, nanoseconds: 1 }),
+      Struct::Duration(Duration { months: 14, days: -1, seconds: 3661, nanoseconds: 5 })
+    ];
+    for s in values {
+      assert_eq!(round_trip(Version::V4_4, false, s.clone()).ok(), Some(s));
+    }
+  }
+
+  #[test]
+  fn displays_iso_8601() {
+    assert_eq!(Date { days: 18690 }.to_string(), "2021-03-04");
+    assert_eq!(Date { days: -719_529 }.to_string(), "-0001-12-31");
+    assert_eq!(Time { nanoseconds: 18_367_000_000_000, tz_offset_seconds: -5400 }.to_string(),
+               "05:06:07-01:30");
+    assert_eq!(LocalTime { nanoseconds: 1_000 }.to_string(), "00:00:00.000001");
+    assert_eq!(DATE_TIME.to_string(), "2021-03-04T05:06:07.5+01:00");
+    assert_eq!(LEGACY_DATE_TIME.to_string(), "2021-03-04T05:06:07.5+01:00");
+    assert_eq!(zone_id().to_string(), "2021-03-04T04:06:07Z[Europe/Paris]");
+    assert_eq!(legacy_zone_id().to_string(), "2021-03-04T05:06:07[Europe/Paris]");
+    assert_eq!(LocalDateTime { seconds: 0, nanoseconds: 0 }.to_string(), "1970-01-01T00:00:00");
+    assert_eq!(Duration { months: 14, days: 3, seconds: 14_706, nanoseconds: 500_000_000 }.to_string(),
+               "P1Y2M3DT4H5M6.5S");
+    assert_eq!(Duration { months: 0, days: 0, seconds: -90, nanoseconds: 0 }.to_string(), "PT-1M-30S");
+    assert_eq!(Duration { months: 0, days: 0, seconds: 0, nanoseconds: 0 }.to_string(), "PT0S");
+  }
+}
@@ -0,0 +1,294 @@
+//! Conversions with the types of `chrono` and `chrono-tz`.
+//!
+//! | Structure                             | `chrono` type                   |
+//! |---------------------------------------|---------------------------------|
+//! | [Date]                                | `NaiveDate`                     |
+//! | [LocalTime]                           | `NaiveTime`                     |
+//! | [Time]                                | `(NaiveTime, FixedOffset)`      |
+//! | [LocalDateTime]                       | `NaiveDateTime`                 |
+//! | [DateTime], [LegacyDateTime]          | `DateTime<FixedOffset>`         |
+//! | [DateTimeZoneId], [LegacyDateTimeZoneId] | `DateTime<Tz>`               |
+//! | [Duration]                            | `chrono::Duration`              |
+//!
+//! Durations counting months fail to convert, days counting 24 hours.
+use std::convert::TryFrom;
+use ::chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use ::chrono_tz::Tz;
+use super::*;
+
+/// Days from the first day of the Common Era to the Unix epoch.
+const EPOCH_DAYS_FROM_CE: i64 = 719_163;
+
+fn offset(seconds: i64) -> Result<FixedOffset, TemporalError> {
+  i32::try_from(seconds)
+    .ok()
+    .and_then(FixedOffset::east_opt)
+    .ok_or(TemporalError::InvalidOffset(seconds))
+}
+
+fn zone(tz_id: &str) -> Result<Tz, TemporalError> {
+  tz_id.parse().map_err(|_| TemporalError::UnknownZone(tz_id.to_owned()))
+}
+
+fn utc(seconds: i64, nanoseconds: i64, target: &'static str)
+       -> Result<::chrono::DateTime<::chrono::Utc>, TemporalError> {
+  u32::try_from(nanoseconds)
+    .ok()
+    .and_then(|nanoseconds| ::chrono::DateTime::from_timestamp(seconds, nanoseconds))
+    .ok_or(TemporalError::OutOfRange(target))
+}
+
+fn naive_time(nanoseconds: i64) -> Result<NaiveTime, TemporalError> {
+  let (seconds, nanoseconds) = split_nanos(nanoseconds.into())
+    .ok_or(TemporalError::OutOfRange("NaiveTime"))?;
+  u32::try_from(seconds)
+    .ok()
+    .and_then(|seconds| NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanoseconds))
+    .ok_or(TemporalError::OutOfRange("NaiveTime"))
+}
+
+fn time_nanos(time: NaiveTime) -> i64 {
+  i64::from(time.num_seconds_from_midnight()) * NANOS_PER_SECOND + i64::from(time.nanosecond())
+}
+
+impl TryFrom<Date> for NaiveDate {
+  type Error = TemporalError;
+
+  fn try_from(x: Date) -> Result<Self, Self::Error> {
+    x.days
+      .checked_add(EPOCH_DAYS_FROM_CE)
+      .and_then(|days| i32::try_from(days).ok())
+      .and_then(NaiveDate::from_num_days_from_ce_opt)
+      .ok_or(TemporalError::OutOfRange("NaiveDate"))
+  }
+}
+
+impl From<NaiveDate> for Date {
+  fn from(x: NaiveDate) -> Self {
+    Date { days: i64::from(::chrono::Datelike::num_days_from_ce(&x)) - EPOCH_DAYS_FROM_CE }
+  }
+}
+
+impl TryFrom<LocalTime> for NaiveTime {
+  type Error = TemporalError;
+
+  fn try_from(x: LocalTime) -> Result<Self, Self::Error> {
+    naive_time(x.nanoseconds)
+  }
+}
+
+impl From<NaiveTime> for LocalTime {
+  fn from(x: NaiveTime) -> Self {
+    LocalTime { nanoseconds: time_nanos(x) }
+  }
+}
+
+impl TryFrom<Time> for (NaiveTime, FixedOffset) {
+  type Error = TemporalError;
+
+  fn try_from(x: Time) -> Result<Self, Self::Error> {
+    Ok((naive_time(x.nanoseconds)?, offset(x.tz_offset_seconds)?))
+  }
+}
+
+impl From<(NaiveTime, FixedOffset)> for Time {
+  fn from((time, offset): (NaiveTime, FixedOffset)) -> Self {
+    Time {
+      nanoseconds: time_nanos(time),
+      tz_offset_seconds: offset.local_minus_utc().into()
+    }
+  }
+}
+
+impl TryFrom<LocalDateTime> for NaiveDateTime {
+  type Error = TemporalError;
+
+  fn try_from(x: LocalDateTime) -> Result<Self, Self::Error> {
+    utc(x.seconds, x.nanoseconds, "NaiveDateTime").map(|x| x.naive_utc())
+  }
+}
+
+impl From<NaiveDateTime> for LocalDateTime {
+  fn from(x: NaiveDateTime) -> Self {
+    let x = x.and_utc();
+    LocalDateTime { seconds: x.timestamp(), nanoseconds: x.timestamp_subsec_nanos().into() }
+  }
+}
+
+impl TryFrom<DateTime> for ::chrono::DateTime<FixedOffset> {
+  type Error = TemporalError;
+
+  fn try_from(x: DateTime) -> Result<Self, Self::Error> {
+    let offset = offset(x.tz_offset_seconds)?;
+    utc(x.seconds, x.nanoseconds, "DateTime<FixedOffset>").map(|x| x.with_timezone(&offset))
+  }
+}
+
+impl<Z: TimeZone> From<::chrono::DateTime<Z>> for DateTime
+  where Z::Offset: ::chrono::Offset {
+  fn from(x: ::chrono::DateTime<Z>) -> Self {
+    DateTime {
+      seconds: x.timestamp(),
+      nanoseconds: x.timestamp_subsec_nanos().into(),
+      tz_offset_seconds: ::chrono::Offset::fix(x.offset()).local_minus_utc().into()
+    }
+  }
+}
+
+impl TryFrom<LegacyDateTime> for ::chrono::DateTime<FixedOffset> {
+  type Error = TemporalError;
+
+  fn try_from(x: LegacyDateTime) -> Result<Self, Self::Error> {
+    Self::try_from(DateTime::from(x))
+  }
+}
+
+impl From<::chrono::DateTime<FixedOffset>> for LegacyDateTime {
+  fn from(x: ::chrono::DateTime<FixedOffset>) -> Self {
+    DateTime::from(x).into()
+  }
+}
+
+impl TryFrom<DateTimeZoneId> for ::chrono::DateTime<Tz> {
+  type Error = TemporalError;
+
+  fn try_from(x: DateTimeZoneId) -> Result<Self, Self::Error> {
+    let zone = zone(&x.tz_id)?;
+    utc(x.seconds, x.nanoseconds, "DateTime<Tz>").map(|x| x.with_timezone(&zone))
+  }
+}
+
+impl From<::chrono::DateTime<Tz>> for DateTimeZoneId {
+  fn from(x: ::chrono::DateTime<Tz>) -> Self {
+    DateTimeZoneId {
+      seconds: x.timestamp(),
+      nanoseconds: x.timestamp_subsec_nanos().into(),
+      tz_id: x.timezone().name().to_owned()
+    }
+  }
+}
+
+/// Fails with [AmbiguousLocalDateTime](TemporalError::AmbiguousLocalDateTime)
+/// when the local date time is skipped or repeated in the zone.
+impl TryFrom<LegacyDateTimeZoneId> for ::chrono::DateTime<Tz> {
+  type Error = TemporalError;
+
+  fn try_from(x: LegacyDateTimeZoneId) -> Result<Self, Self::Error> {
+    let zone = zone(&x.tz_id)?;
+    let local = utc(x.seconds, x.nanoseconds, "DateTime<Tz>")?.naive_utc();
+    zone.from_local_datetime(&local)
+      .single()
+      .ok_or(TemporalError::AmbiguousLocalDateTime)
+  }
+}
+
+impl From<::chrono::DateTime<Tz>> for LegacyDateTimeZoneId {
+  fn from(x: ::chrono::DateTime<Tz>) -> Self {
+    let local = x.naive_local().and_utc();
+    LegacyDateTimeZoneId {
+      seconds: local.timestamp(),
+      nanoseconds: local.timestamp_subsec_nanos().into(),
+      tz_id: x.timezone().name().to_owned()
+    }
+  }
+}
+
+/// Fails with [DurationWithMonths](TemporalError::DurationWithMonths) when
+/// the duration counts months.
+impl TryFrom<Duration> for ::chrono::Duration {
+  type Error = TemporalError;
+
+  fn try_from(x: Duration) -> Result<Self, Self::Error> {
+    split_nanos(duration_nanos(&x)?)
+      .and_then(|(seconds, nanoseconds)| ::chrono::Duration::new(seconds, nanoseconds))
+      .ok_or(TemporalError::OutOfRange("Duration"))
+  }
+}
+
+impl From<::chrono::Duration> for Duration {
+  fn from(x: ::chrono::Duration) -> Self {
+    // The nanoseconds of negative durations are negative too.
+    let (seconds, nanoseconds) = match x.subsec_nanos() {
+      n if n < 0 => (x.num_seconds() - 1, i64::from(n) + NANOS_PER_SECOND),
+      n => (x.num_seconds(), i64::from(n))
+    };
+    Duration { months: 0, days: 0, seconds, nanoseconds }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date_time(s: &str) -> ::chrono::DateTime<FixedOffset> {
+    ::chrono::DateTime::parse_from_rfc3339(s).unwrap()
+  }
+
+  #[test]
+  fn converts_dates_and_times() {
+    let date = NaiveDate::from_ymd_opt(1969, 12, 31).unwrap();
+    assert_eq!(Date::from(date), Date { days: -1 });
+    assert_eq!(NaiveDate::try_from(Date { days: -1 }), Ok(date));
+    assert_eq!(NaiveDate::try_from(Date { days: i64::MAX }), Err(TemporalError::OutOfRange("NaiveDate")));
+
+    let time = NaiveTime::from_hms_nano_opt(5, 6, 7, 8).unwrap();
+    let local = LocalTime { nanoseconds: 18_367_000_000_008 };
+    assert_eq!(LocalTime::from(time), local);
+    assert_eq!(NaiveTime::try_from(local), Ok(time));
+    assert_eq!(NaiveTime::try_from(LocalTime { nanoseconds: -1 }),
+               Err(TemporalError::OutOfRange("NaiveTime")));
+
+    let offset = FixedOffset::east_opt(-5400).unwrap();
+    let with_offset = Time { nanoseconds: local.nanoseconds, tz_offset_seconds: -5400 };
+    assert_eq!(Time::from((time, offset)), with_offset);
+    assert_eq!(<(NaiveTime, FixedOffset)>::try_from(with_offset), Ok((time, offset)));
+    assert_eq!(<(NaiveTime, FixedOffset)>::try_from(Time { nanoseconds: 0, tz_offset_seconds: 86_400 }),
+               Err(TemporalError::InvalidOffset(86_400)));
+
+    let naive = date.and_time(time);
+    let local_date_time = LocalDateTime { seconds: -68_033, nanoseconds: 8 };
+    assert_eq!(LocalDateTime::from(naive), local_date_time);
+    assert_eq!(NaiveDateTime::try_from(local_date_time), Ok(naive));
+  }
+
+  #[test]
+  fn converts_date_times_with_offsets() {
+    let chrono = date_time("2021-03-04T05:06:07.5+01:00");
+    let bolt = DateTime { seconds: 1_614_830_767, nanoseconds: 500_000_000, tz_offset_seconds: 3600 };
+    assert_eq!(DateTime::from(chrono), bolt);
+    assert_eq!(::chrono::DateTime::<FixedOffset>::try_from(bolt), Ok(chrono));
+    assert_eq!(LegacyDateTime::from(chrono), LegacyDateTime::from(bolt));
+    assert_eq!(::chrono::DateTime::<FixedOffset>::try_from(LegacyDateTime::from(bolt)), Ok(chrono));
+  }
+
+  #[test]
+  fn converts_date_times_with_zones() {
+    let paris: Tz = "Europe/Paris".parse().unwrap();
+    let chrono = date_time("2021-03-04T05:06:07+01:00").with_timezone(&paris);
+    let zone_id = DateTimeZoneId { seconds: 1_614_830_767, nanoseconds: 0, tz_id: "Europe/Paris".to_owned() };
+    let legacy = LegacyDateTimeZoneId { seconds: 1_614_834_367, nanoseconds: 0, tz_id: "Europe/Paris".to_owned() };
+    assert_eq!(DateTimeZoneId::from(chrono), zone_id);
+    assert_eq!(::chrono::DateTime::<Tz>::try_from(zone_id), Ok(chrono));
+    assert_eq!(LegacyDateTimeZoneId::from(chrono), legacy);
+    assert_eq!(::chrono::DateTime::<Tz>::try_from(legacy), Ok(chrono));
+
+    // 2021-03-28T02:30:00 is skipped by the change to summer time.
+    let skipped = LegacyDateTimeZoneId { seconds: 1_616_898_600, nanoseconds: 0, tz_id: "Europe/Paris".to_owned() };
+    assert_eq!(::chrono::DateTime::<Tz>::try_from(skipped), Err(TemporalError::AmbiguousLocalDateTime));
+    let unknown = DateTimeZoneId { seconds: 0, nanoseconds: 0, tz_id: "Mars/Olympus".to_owned() };
+    assert_eq!(::chrono::DateTime::<Tz>::try_from(unknown),
+               Err(TemporalError::UnknownZone("Mars/Olympus".to_owned())));
+  }
+
+  #[test]
+  fn converts_durations() {
+    let negative = ::chrono::Duration::milliseconds(-1500);
+    let bolt = Duration { months: 0, days: 0, seconds: -2, nanoseconds: 500_000_000 };
+    assert_eq!(Duration::from(negative), bolt);
+    assert_eq!(::chrono::Duration::try_from(bolt), Ok(negative));
+    assert_eq!(::chrono::Duration::try_from(Duration { months: 0, days: 1, seconds: 1, nanoseconds: 0 }),
+               Ok(::chrono::Duration::seconds(86_401)));
+    assert_eq!(::chrono::Duration::try_from(Duration { months: 1, days: 0, seconds: 0, nanoseconds: 0 }),
+               Err(TemporalError::DurationWithMonths(1)));
+  }
+}
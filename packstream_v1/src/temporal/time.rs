@@ -0,0 +1,243 @@
+//! Conversions with the types of `time`.
+//!
+//! | Structure                    | `time` type                     |
+//! |------------------------------|---------------------------------|
+//! | [Date]                       | `Date`                          |
+//! | [LocalTime]                  | `Time`                          |
+//! | [Time]                       | `(Time, UtcOffset)`             |
+//! | [LocalDateTime]              | `PrimitiveDateTime`             |
+//! | [DateTime], [LegacyDateTime] | `OffsetDateTime`                |
+//! | [Duration]                   | `time::Duration`                |
+//!
+//! `time` has no time zone database, so date times with a zone id have no
+//! equivalent. Durations counting months fail to convert, days counting 24
+//! hours.
+use std::convert::TryFrom;
+use ::time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+use super::*;
+
+/// Julian day of the Unix epoch.
+const EPOCH_JULIAN_DAY: i64 = 2_440_588;
+
+fn offset(seconds: i64) -> Result<UtcOffset, TemporalError> {
+  i32::try_from(seconds)
+    .ok()
+    .and_then(|seconds| UtcOffset::from_whole_seconds(seconds).ok())
+    .ok_or(TemporalError::InvalidOffset(seconds))
+}
+
+fn utc(seconds: i64, nanoseconds: i64, target: &'static str) -> Result<OffsetDateTime, TemporalError> {
+  let nanos = i128::from(seconds) * i128::from(NANOS_PER_SECOND) + i128::from(nanoseconds);
+  OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|_| TemporalError::OutOfRange(target))
+}
+
+fn time_of_day(nanoseconds: i64) -> Result<::time::Time, TemporalError> {
+  let out_of_range = TemporalError::OutOfRange("Time");
+  if !(0..SECONDS_PER_DAY * NANOS_PER_SECOND).contains(&nanoseconds) {
+    return Err(out_of_range);
+  }
+  let seconds = nanoseconds / NANOS_PER_SECOND;
+  ::time::Time::from_hms_nano(
+    (seconds / 3600) as u8,
+    (seconds / 60 % 60) as u8,
+    (seconds % 60) as u8,
+    (nanoseconds % NANOS_PER_SECOND) as u32
+  ).map_err(|_| out_of_range)
+}
+
+fn time_nanos(time: ::time::Time) -> i64 {
+  let (hours, minutes, seconds, nanoseconds) = time.as_hms_nano();
+  let seconds = i64::from(hours) * 3600 + i64::from(minutes) * 60 + i64::from(seconds);
+  seconds * NANOS_PER_SECOND + i64::from(nanoseconds)
+}
+
+impl TryFrom<Date> for ::time::Date {
+  type Error = TemporalError;
+
+  fn try_from(x: Date) -> Result<Self, Self::Error> {
+    x.days
+      .checked_add(EPOCH_JULIAN_DAY)
+      .and_then(|day| i32::try_from(day).ok())
+      .and_then(|day| ::time::Date::from_julian_day(day).ok())
+      .ok_or(TemporalError::OutOfRange("Date"))
+  }
+}
+
+impl From<::time::Date> for Date {
+  fn from(x: ::time::Date) -> Self {
+    Date { days: i64::from(x.to_julian_day()) - EPOCH_JULIAN_DAY }
+  }
+}
+
+impl TryFrom<LocalTime> for ::time::Time {
+  type Error = TemporalError;
+
+  fn try_from(x: LocalTime) -> Result<Self, Self::Error> {
+    time_of_day(x.nanoseconds)
+  }
+}
+
+impl From<::time::Time> for LocalTime {
+  fn from(x: ::time::Time) -> Self {
+    LocalTime { nanoseconds: time_nanos(x) }
+  }
+}
+
+impl TryFrom<Time> for (::time::Time, UtcOffset) {
+  type Error = TemporalError;
+
+  fn try_from(x: Time) -> Result<Self, Self::Error> {
+    Ok((time_of_day(x.nanoseconds)?, offset(x.tz_offset_seconds)?))
+  }
+}
+
+impl From<(::time::Time, UtcOffset)> for Time {
+  fn from((time, offset): (::time::Time, UtcOffset)) -> Self {
+    Time {
+      nanoseconds: time_nanos(time),
+      tz_offset_seconds: offset.whole_seconds().into()
+    }
+  }
+}
+
+impl TryFrom<LocalDateTime> for PrimitiveDateTime {
+  type Error = TemporalError;
+
+  fn try_from(x: LocalDateTime) -> Result<Self, Self::Error> {
+    utc(x.seconds, x.nanoseconds, "PrimitiveDateTime")
+      .map(|x| PrimitiveDateTime::new(x.date(), x.time()))
+  }
+}
+
+impl From<PrimitiveDateTime> for LocalDateTime {
+  fn from(x: PrimitiveDateTime) -> Self {
+    let x = x.assume_utc();
+    LocalDateTime { seconds: x.unix_timestamp(), nanoseconds: x.nanosecond().into() }
+  }
+}
+
+impl TryFrom<DateTime> for OffsetDateTime {
+  type Error = TemporalError;
+
+  fn try_from(x: DateTime) -> Result<Self, Self::Error> {
+    let offset = offset(x.tz_offset_seconds)?;
+    utc(x.seconds, x.nanoseconds, "OffsetDateTime")?
+      .checked_to_offset(offset)
+      .ok_or(TemporalError::OutOfRange("OffsetDateTime"))
+  }
+}
+
+impl From<OffsetDateTime> for DateTime {
+  fn from(x: OffsetDateTime) -> Self {
+    DateTime {
+      seconds: x.unix_timestamp(),
+      nanoseconds: x.nanosecond().into(),
+      tz_offset_seconds: x.offset().whole_seconds().into()
+    }
+  }
+}
+
+impl TryFrom<LegacyDateTime> for OffsetDateTime {
+  type Error = TemporalError;
+
+  fn try_from(x: LegacyDateTime) -> Result<Self, Self::Error> {
+    Self::try_from(DateTime::from(x))
+  }
+}
+
+impl From<OffsetDateTime> for LegacyDateTime {
+  fn from(x: OffsetDateTime) -> Self {
+    DateTime::from(x).into()
+  }
+}
+
+/// Fails with [DurationWithMonths](TemporalError::DurationWithMonths) when
+/// the duration counts months.
+impl TryFrom<Duration> for ::time::Duration {
+  type Error = TemporalError;
+
+  fn try_from(x: Duration) -> Result<Self, Self::Error> {
+    split_nanos(duration_nanos(&x)?)
+      .map(|(seconds, nanoseconds)| ::time::Duration::new(seconds, nanoseconds as i32))
+      .ok_or(TemporalError::OutOfRange("Duration"))
+  }
+}
+
+/// Fails for the few durations around `time::Duration::MIN` whose seconds
+/// don't fit once the nanoseconds are made positive.
+impl TryFrom<::time::Duration> for Duration {
+  type Error = TemporalError;
+
+  fn try_from(x: ::time::Duration) -> Result<Self, Self::Error> {
+    split_nanos(x.whole_nanoseconds())
+      .map(|(seconds, nanoseconds)| Duration {
+        months: 0,
+        days: 0,
+        seconds,
+        nanoseconds: nanoseconds.into()
+      })
+      .ok_or(TemporalError::OutOfRange("Duration"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ::time::Month;
+  use super::*;
+
+  fn date() -> ::time::Date {
+    ::time::Date::from_calendar_date(1969, Month::December, 31).unwrap()
+  }
+
+  fn time() -> ::time::Time {
+    ::time::Time::from_hms_nano(5, 6, 7, 8).unwrap()
+  }
+
+  #[test]
+  fn converts_dates_and_times() {
+    assert_eq!(Date::from(date()), Date { days: -1 });
+    assert_eq!(::time::Date::try_from(Date { days: -1 }), Ok(date()));
+    assert_eq!(::time::Date::try_from(Date { days: i64::MAX }), Err(TemporalError::OutOfRange("Date")));
+
+    let local = LocalTime { nanoseconds: 18_367_000_000_008 };
+    assert_eq!(LocalTime::from(time()), local);
+    assert_eq!(::time::Time::try_from(local), Ok(time()));
+    assert_eq!(::time::Time::try_from(LocalTime { nanoseconds: SECONDS_PER_DAY * NANOS_PER_SECOND }),
+               Err(TemporalError::OutOfRange("Time")));
+
+    let offset = UtcOffset::from_whole_seconds(-5400).unwrap();
+    let with_offset = Time { nanoseconds: local.nanoseconds, tz_offset_seconds: -5400 };
+    assert_eq!(Time::from((time(), offset)), with_offset);
+    assert_eq!(<(::time::Time, UtcOffset)>::try_from(with_offset), Ok((time(), offset)));
+
+    let primitive = PrimitiveDateTime::new(date(), time());
+    let local_date_time = LocalDateTime { seconds: -68_033, nanoseconds: 8 };
+    assert_eq!(LocalDateTime::from(primitive), local_date_time);
+    assert_eq!(PrimitiveDateTime::try_from(local_date_time), Ok(primitive));
+  }
+
+  #[test]
+  fn converts_date_times_with_offsets() {
+    let offset = UtcOffset::from_whole_seconds(3600).unwrap();
+    let date = ::time::Date::from_calendar_date(2021, Month::March, 4).unwrap();
+    let time = ::time::Time::from_hms_milli(5, 6, 7, 500).unwrap();
+    let offset_date_time = PrimitiveDateTime::new(date, time).assume_offset(offset);
+    let bolt = DateTime { seconds: 1_614_830_767, nanoseconds: 500_000_000, tz_offset_seconds: 3600 };
+    assert_eq!(DateTime::from(offset_date_time), bolt);
+    assert_eq!(OffsetDateTime::try_from(bolt), Ok(offset_date_time));
+    assert_eq!(LegacyDateTime::from(offset_date_time), LegacyDateTime::from(bolt));
+    assert_eq!(OffsetDateTime::try_from(LegacyDateTime::from(bolt)), Ok(offset_date_time));
+    assert_eq!(OffsetDateTime::try_from(DateTime { tz_offset_seconds: 100_000, ..bolt }),
+               Err(TemporalError::InvalidOffset(100_000)));
+  }
+
+  #[test]
+  fn converts_durations() {
+    let negative = ::time::Duration::milliseconds(-1500);
+    let bolt = Duration { months: 0, days: 0, seconds: -2, nanoseconds: 500_000_000 };
+    assert_eq!(Duration::try_from(negative), Ok(bolt));
+    assert_eq!(::time::Duration::try_from(bolt), Ok(negative));
+    assert_eq!(::time::Duration::try_from(Duration { months: 2, days: 0, seconds: 0, nanoseconds: 0 }),
+               Err(TemporalError::DurationWithMonths(2)));
+  }
+}
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
-use crate::structs::Struct;
+use crate::structs::{Struct, Node, Relationship, Path, UnboundRelationship};
+use crate::convert::ConversionError;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
   Null,
   Boolean (bool),
@@ -12,3 +13,128 @@ pub enum Value {
   Map     (HashMap<String, Value>),
   Struct  (Struct)
 }
+
+/// Typed accessors, failing with
+/// [TypeMismatch](ConversionError::TypeMismatch) when the value holds
+/// another type.
+impl Value {
+  pub fn is_null(&self) -> bool {
+    matches!(self, Value::Null)
+  }
+
+  pub fn as_bool(&self) -> Result<bool, ConversionError> {
+    match self {
+      Value::Boolean(b) => Ok(*b),
+      x => Err(ConversionError::mismatch("Boolean", x))
+    }
+  }
+
+  pub fn as_i64(&self) -> Result<i64, ConversionError> {
+    match self {
+      Value::Int(n) => Ok(*n),
+      x => Err(ConversionError::mismatch("Int", x))
+    }
+  }
+
+  pub fn as_f64(&self) -> Result<f64, ConversionError> {
+    match self {
+      Value::Float(n) => Ok(*n),
+      x => Err(ConversionError::mismatch("Float", x))
+    }
+  }
+
+  pub fn as_str(&self) -> Result<&str, ConversionError> {
+    match self {
+      Value::String(cs) => Ok(cs),
+      x => Err(ConversionError::mismatch("String", x))
+    }
+  }
+
+  pub fn as_list(&self) -> Result<&[Value], ConversionError> {
+    match self {
+      Value::List(xs) => Ok(xs),
+      x => Err(ConversionError::mismatch("List", x))
+    }
+  }
+
+  pub fn as_map(&self) -> Result<&HashMap<String, Value>, ConversionError> {
+    match self {
+      Value::Map(ps) => Ok(ps),
+      x => Err(ConversionError::mismatch("Map", x))
+    }
+  }
+
+  pub fn as_struct(&self) -> Result<&Struct, ConversionError> {
+    match self {
+      Value::Struct(s) => Ok(s),
+      x => Err(ConversionError::mismatch("Struct", x))
+    }
+  }
+
+  pub fn as_node(&self) -> Result<&Node, ConversionError> {
+    match self {
+      Value::Struct(Struct::Node(n)) => Ok(n),
+      x => Err(ConversionError::mismatch("Node", x))
+    }
+  }
+
+  pub fn as_relationship(&self) -> Result<&Relationship, ConversionError> {
+    match self {
+      Value::Struct(Struct::Relationship(r)) => Ok(r),
+      x => Err(ConversionError::mismatch("Relationship", x))
+    }
+  }
+
+  pub fn as_unbound_relationship(&self) -> Result<&UnboundRelationship, ConversionError> {
+    match self {
+      Value::Struct(Struct::UnboundRelationship(r)) => Ok(r),
+      x => Err(ConversionError::mismatch("UnboundRelationship", x))
+    }
+  }
+
+  pub fn as_path(&self) -> Result<&Path, ConversionError> {
+    match self {
+      Value::Struct(Struct::Path(p)) => Ok(p),
+      x => Err(ConversionError::mismatch("Path", x))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn gets_typed_values() {
+    assert!(Value::Null.is_null());
+    assert_eq!(Value::Boolean(true).as_bool(), Ok(true));
+    assert_eq!(Value::Int(1).as_i64(), Ok(1));
+    assert_eq!(Value::Float(0.5).as_f64(), Ok(0.5));
+    assert_eq!(Value::from("a").as_str(), Ok("a"));
+    assert_eq!(Value::from(vec![1]).as_list(), Ok(&[Value::Int(1)][..]));
+    assert_eq!(Value::Map(HashMap::new()).as_map(), Ok(&HashMap::new()));
+
+    let node = || Node { id: 1, labels: vec![], properties: HashMap::new(), element_id: None };
+    let value = Value::Struct(Struct::Node(node()));
+    assert_eq!(value.as_node(), Ok(&node()));
+    assert_eq!(value.as_struct(), Ok(&Struct::Node(node())));
+  }
+
+  #[test]
+  fn rejects_other_types() {
+    assert!(!Value::Int(0).is_null());
+    assert_eq!(Value::Int(1).as_str(), Err(ConversionError::TypeMismatch { expected: "String", found: "Int" }));
+    assert_eq!(Value::Float(1.0).as_i64(), Err(ConversionError::TypeMismatch { expected: "Int", found: "Float" }));
+    assert_eq!(Value::Null.as_list(), Err(ConversionError::TypeMismatch { expected: "List", found: "Null" }));
+    assert_eq!(Value::from(vec![1]).as_map(), Err(ConversionError::TypeMismatch { expected: "Map", found: "List" }));
+
+    let path = Value::Struct(Struct::Path(Path { nodes: vec![], relationships: vec![], sequence: vec![] }));
+    assert!(path.as_path().is_ok());
+    assert_eq!(path.as_node(), Err(ConversionError::TypeMismatch { expected: "Node", found: "Path" }));
+    assert_eq!(path.as_relationship(),
+               Err(ConversionError::TypeMismatch { expected: "Relationship", found: "Path" }));
+    assert_eq!(path.as_unbound_relationship(),
+               Err(ConversionError::TypeMismatch { expected: "UnboundRelationship", found: "Path" }));
+    assert_eq!(Value::Null.as_struct(), Err(ConversionError::TypeMismatch { expected: "Struct", found: "Null" }));
+  }
+}